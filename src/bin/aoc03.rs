@@ -17,6 +17,8 @@ extern crate itertools;
 
 use std::convert::{identity, TryInto};
 
+use mbp_aoc2019::point;
+
 pub fn main() {
     println!("03a: {}", solve_a());
     println!("03b: {}", solve_b());
@@ -46,13 +48,6 @@ fn load_input() -> String {
     std::fs::read_to_string("input/input03.txt").unwrap()
 }
 
-// x left-to-right, y top-to-bottom
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
-struct Point {
-    x: isize,
-    y: isize,
-}
-
 #[derive(Debug)]
 enum Wire {
     Horz {
@@ -110,7 +105,7 @@ impl Wire {
                     None
                 } else {
                     // dbg!((vx, hy));
-                    let man_dist = (vx.abs() + hy.abs()).try_into().unwrap();
+                    let man_dist = point(*vx, *hy).manhattan(point(0, 0)).try_into().unwrap();
                     let path_dist = vd + hd + (hy - ey).abs() + (vx - ex).abs();
                     Some((man_dist, path_dist.try_into().unwrap()))
                 }
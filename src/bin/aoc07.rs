@@ -14,8 +14,9 @@
 
 use std::convert::TryInto;
 
+use mbp_aoc2019::brute_force::par_max_by_key;
 use mbp_aoc2019::intcode::{parse_string, Computer};
-use mbp_aoc2019::permute::permutations;
+use mbp_aoc2019::permute::Permutations;
 
 pub fn main() {
     println!("07a: {:?}", solve_a().0);
@@ -28,18 +29,10 @@ fn solve_a() -> (isize, Vec<usize>) {
 
 fn solve_type_a(prog: &str) -> (isize, Vec<usize>) {
     let prog = parse_string(prog);
-    let mut best_pow = 0;
-    let mut best_phases = Vec::new();
-
-    for phases in permutations(0..5) {
-        let pow = run_pipeline(&phases, &prog);
-        // println!("{:?} => {}", &phases, pow);
-        if pow > best_pow {
-            best_pow = pow;
-            best_phases = phases.clone();
-        }
-    }
-    (best_pow, best_phases)
+    par_max_by_key(Permutations::new(0..5), |phases| {
+        run_pipeline(phases, &prog)
+    })
+    .unwrap()
 }
 
 fn solve_b() -> (isize, Vec<usize>) {
@@ -48,18 +41,10 @@ fn solve_b() -> (isize, Vec<usize>) {
 
 fn solve_type_b(prog: &str) -> (isize, Vec<usize>) {
     let prog = parse_string(prog);
-    let mut best_pow = 0;
-    let mut best_phases = Vec::new();
-
-    for phases in permutations(5..=9) {
-        let pow = run_feedback(&phases, &prog);
-        // println!("{:?} => {}", &phases, pow);
-        if pow > best_pow {
-            best_pow = pow;
-            best_phases = phases.clone();
-        }
-    }
-    (best_pow, best_phases)
+    par_max_by_key(Permutations::new(5..=9), |phases| {
+        run_feedback(phases, &prog)
+    })
+    .unwrap()
 }
 
 fn load_input() -> String {
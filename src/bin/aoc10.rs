@@ -16,10 +16,9 @@
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 
-// Track angles as minimal ratios?
+use mbp_aoc2019::{point, Point};
 
-/// x, y; origin at top left
-type Point = (isize, isize);
+// Track angles as minimal ratios?
 
 pub fn main() {
     println!("10a: {}", solve_a());
@@ -56,8 +55,8 @@ fn solve_type_b(s: &str) -> isize {
 
     let mut q = BTreeMap::<NF64, BTreeSet<(NF64, Point)>>::new();
     for p in asts {
-        let d = (p.0 - obs.0, p.1 - obs.1);
-        if d == (0, 0) {
+        let d = p - obs;
+        if d == point(0, 0) {
             continue;
         }
         let (a, r) = to_polar(d);
@@ -83,7 +82,7 @@ fn solve_type_b(s: &str) -> isize {
         }
     }
     let p200 = zz[199];
-    p200.0 * 100 + p200.1
+    p200.x * 100 + p200.y
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -114,8 +113,8 @@ impl PartialEq for NF64 {
 /// Given a coordinate in integers, return angle clockwise from up
 /// in radians, and distance in units.
 fn to_polar(p: Point) -> (NF64, NF64) {
-    let x = p.0 as f64;
-    let y = p.1 as f64;
+    let x = p.x as f64;
+    let y = p.y as f64;
     let mut ang = NF64(x.atan2(-y));
     if ang.0 < 0f64 {
         ang.0 += 2f64 * std::f64::consts::PI;
@@ -135,8 +134,8 @@ fn visible(asts: &[Point], obs: Point) -> usize {
     // How many are unique?
     let uniq = asts
         .iter()
-        .map(|a| (a.0 - obs.0, a.1 - obs.1))
-        .filter(|d| *d != (0, 0))
+        .map(|a| *a - obs)
+        .filter(|d| *d != point(0, 0))
         .map(|d| to_polar(d).0)
         .collect::<BTreeSet<_>>();
     // println!("from {:?} can see {}: {:?}", obs, uniq.len(), &uniq);
@@ -148,7 +147,7 @@ fn parse(s: &str) -> Vec<Point> {
     for (y, l) in s.lines().map(str::trim).enumerate() {
         for (x, c) in l.chars().enumerate() {
             match c {
-                '#' => v.push((x as isize, y as isize)),
+                '#' => v.push(point(x as isize, y as isize)),
                 '.' => (),
                 _ => panic!("unexpected char {:?}", c),
             }
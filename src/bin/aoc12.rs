@@ -18,12 +18,12 @@ use std::fmt;
 
 use num_integer::Integer;
 
-use mbp_aoc2019::ordering_to_int;
+use mbp_aoc2019::point_n::{Point3, PointN};
 
 #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Debug)]
 struct Moon {
-    pos: [isize; 3],
-    vel: [isize; 3],
+    pos: Point3,
+    vel: Point3,
 }
 
 pub fn main() {
@@ -71,14 +71,13 @@ fn solve_b() -> usize {
 impl Moon {
     fn new(pos: [isize; 3]) -> Moon {
         Moon {
-            pos,
-            vel: [0, 0, 0],
+            pos: PointN(pos),
+            vel: Point3::zero(),
         }
     }
 
     fn energy(&self) -> isize {
-        self.pos.iter().cloned().map(isize::abs).sum::<isize>()
-            * self.vel.iter().cloned().map(isize::abs).sum::<isize>()
+        self.pos.abs_sum() * self.vel.abs_sum()
     }
 }
 
@@ -100,20 +99,15 @@ fn step(ms: &mut [Moon]) {
         for j in 0..ms.len() {
             if i != j {
                 let pi = ms[i].pos;
-                let mut mj = &mut ms[j];
-
-                mj.vel[0] += ordering_to_int(pi[0].cmp(&mj.pos[0]));
-                mj.vel[1] += ordering_to_int(pi[1].cmp(&mj.pos[1]));
-                mj.vel[2] += ordering_to_int(pi[2].cmp(&mj.pos[2]));
+                let mj = &mut ms[j];
+                mj.vel += (pi - mj.pos).signum();
             }
         }
     }
 
     // velocity
     for m in ms.iter_mut() {
-        m.pos[0] += m.vel[0];
-        m.pos[1] += m.vel[1];
-        m.pos[2] += m.vel[2];
+        m.pos += m.vel;
     }
 }
 
@@ -136,17 +130,17 @@ mod test {
 
         // after 1 step
         step(&mut ms);
-        assert_eq!(ms[0].pos, [2, -1, 1]);
-        assert_eq!(ms[0].vel, [3, -1, -1]);
+        assert_eq!(ms[0].pos, PointN([2, -1, 1]));
+        assert_eq!(ms[0].vel, PointN([3, -1, -1]));
 
-        assert_eq!(ms[1].vel, [1, 3, 3]);
-        assert_eq!(ms[1].pos, [3, -7, -4]);
+        assert_eq!(ms[1].vel, PointN([1, 3, 3]));
+        assert_eq!(ms[1].pos, PointN([3, -7, -4]));
 
-        assert_eq!(ms[2].vel, [-3, 1, -3]);
-        assert_eq!(ms[2].pos, [1, -7, 5]);
+        assert_eq!(ms[2].vel, PointN([-3, 1, -3]));
+        assert_eq!(ms[2].pos, PointN([1, -7, 5]));
 
-        assert_eq!(ms[3].pos, [2, 2, 0]);
-        assert_eq!(ms[3].vel, [-1, -3, 1]);
+        assert_eq!(ms[3].pos, PointN([2, 2, 0]));
+        assert_eq!(ms[3].vel, PointN([-1, -3, 1]));
 
         for _ in 2..=10 {
             step(&mut ms);
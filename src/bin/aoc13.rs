@@ -12,29 +12,55 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-extern crate console;
-
-use std::collections::{BTreeMap, BTreeSet};
-
-use console::Term;
-
-use mbp_aoc2019::intcode::Computer;
-use mbp_aoc2019::ordering_to_int;
+use mbp_aoc2019::breakout::{Breakout, FollowBall, Predictive, Recorder, Strategy, TermRenderer};
 
+/// Run with `--record FILE` to also write every frame to a file, `--fps N`
+/// and `--every N` to show N frames a second or only every Nth frame, and
+/// `--predictive` to move the paddle to where the ball will land rather
+/// than following it.
 pub fn main() {
-    let b = solve_b(true);
+    let args: Vec<String> = std::env::args().collect();
+    let value = |name: &str| {
+        args.iter().position(|a| a == name).map(|i| {
+            args.get(i + 1)
+                .unwrap_or_else(|| panic!("{} needs a value", name))
+                .as_str()
+        })
+    };
+    let number = |name: &str| {
+        value(name).map(|v| {
+            v.parse::<u32>()
+                .unwrap_or_else(|_| panic!("{} needs a number, not {:?}", name, v))
+        })
+    };
+    let mut renderer = TermRenderer::new();
+    if let Some(fps) = number("--fps") {
+        renderer = renderer.with_fps(fps);
+    }
+    if let Some(every) = number("--every") {
+        renderer = renderer.with_every(every as usize);
+    }
+    let mut strategy: Box<dyn Strategy> = if args.iter().any(|a| a == "--predictive") {
+        Box::new(Predictive::default())
+    } else {
+        Box::new(FollowBall)
+    };
+    let b = solve_b(Some(renderer), strategy.as_mut(), value("--record"));
     println!("13a: {}", solve_a());
     println!("13b: {}", b);
 }
 
 fn solve_a() -> usize {
-    let mut g = Game::load();
-    g.c.run();
-    g.consume_output();
-    g.m.values().filter(|t| **t == 2).count()
+    let mut g = Breakout::from_file("input/input13.txt");
+    g.advance();
+    g.count_blocks()
 }
 
-fn solve_b(draw: bool) -> isize {
+fn solve_b(
+    mut renderer: Option<TermRenderer>,
+    strategy: &mut dyn Strategy,
+    record: Option<&str>,
+) -> isize {
     // Playing the game successfully actually turns out to be really
     // simple: just keep the paddle under the balls.
     //
@@ -43,131 +69,21 @@ fn solve_b(draw: bool) -> isize {
     // and rewind if we get something wrong. But it turns out to be
     // unnecessary.)
 
-    let mut g = Game::load();
-    let console = &mut Term::stdout();
-
-    g.c.poke_at(0, 2); // Insert a coin :D
-    if draw {
-        console.clear_screen().unwrap();
-    }
-    loop {
-        g.c.run();
-        if g.c.output_len() > 0 {
-            g.consume_output();
-            if draw {
-                console.move_cursor_to(0, 0).unwrap();
-                g.draw(console).unwrap();
-                // std::thread::sleep(std::time::Duration::from_millis(500));
-            }
-        }
-        if g.c.wants_input() {
-            // Try to keep the paddle under the ball.
-            g.c.push_input(ordering_to_int(
-                g.ball_pos.unwrap().0.cmp(&g.paddle_pos.unwrap().0),
-            ));
-        }
-        if g.c.is_halted() {
-            return g.score;
-        }
-    }
-}
-
-struct Game {
-    c: Computer,
-    m: BTreeMap<(isize, isize), isize>,
-    score: isize,
-
-    // Positions of remaining blocks
-    blocks: BTreeSet<Point>,
-    // Position of paddle
-    paddle_pos: Option<Point>,
-    ball_pos: Option<Point>,
-}
-
-type Point = (isize, isize);
-
-const WALL: isize = 1;
-const BLOCK: isize = 2;
-const PADDLE: isize = 3;
-const BALL: isize = 4;
-
-impl Game {
-    fn load() -> Game {
-        Game {
-            c: Computer::from_file("input/input13.txt"),
-            m: BTreeMap::new(),
-            score: -1,
-            blocks: BTreeSet::new(),
-            paddle_pos: None,
-            ball_pos: None,
+    let mut g = Breakout::from_file("input/input13.txt");
+    let mut recorder = record.map(|path| Recorder::create(path).unwrap());
+    g.insert_coins(); // Insert a coin :D
+    let score = g.play(strategy, |g| {
+        if let Some(renderer) = renderer.as_mut() {
+            renderer.show(g).unwrap();
         }
-    }
-
-    fn consume_output(&mut self) {
-        for chunk in self.c.drain_output().chunks(3) {
-            if let [x, y, t] = chunk {
-                let x = *x;
-                let y = *y;
-                let t = *t;
-                // dbg!(&x, &y, &tile_id);
-                if x == -1 && y == 0 {
-                    self.score = t;
-                } else {
-                    self.m.insert((x, y), t);
-                    if t == PADDLE {
-                        self.paddle_pos = Some((x, y))
-                    } else if t == BALL {
-                        self.ball_pos = Some((x, y));
-                    }
-
-                    if t == BLOCK {
-                        self.blocks.insert((x, y));
-                    } else {
-                        self.blocks.remove(&(x, y));
-                    }
-                }
-            } else {
-                panic!("bad chunk? {:?}", chunk);
-            }
-        }
-    }
-
-    fn draw(&self, w: &mut dyn std::io::Write) -> std::io::Result<()> {
-        writeln!(w, "Score: {}", self.score)?;
-        let mut s = String::new();
-        let ((xmin, xmax), (ymin, ymax)) = self.bounds();
-        assert_eq!(ymin, 0);
-        assert_eq!(xmin, 0);
-        for y in ymin..=ymax {
-            for x in xmin..=xmax {
-                if let Some(t) = self.m.get(&(x, y)) {
-                    s.push(match *t {
-                        0 => ' ',
-                        WALL => '#',
-                        BLOCK => '.',
-                        PADDLE => '_',
-                        BALL => '@',
-                        _ => panic!(),
-                    });
-                }
-            }
-            s.push('\n');
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(g).unwrap();
         }
-        w.write_all(s.as_bytes())
-    }
-
-    fn bounds(&self) -> ((isize, isize), (isize, isize)) {
-        (
-            (
-                self.m.keys().map(|i| i.0).min().unwrap(),
-                self.m.keys().map(|i| i.0).max().unwrap(),
-            ),
-            (
-                self.m.keys().map(|i| i.1).min().unwrap(),
-                self.m.keys().map(|i| i.1).max().unwrap(),
-            ),
-        )
+    });
+    if let Some(recorder) = recorder {
+        recorder.finish().unwrap();
     }
+    score
 }
 
 #[cfg(test)]
@@ -181,6 +97,7 @@ mod test {
 
     #[test]
     fn solution_b() {
-        assert_eq!(solve_b(false), 10776);
+        assert_eq!(solve_b(None, &mut FollowBall, None), 10776);
+        assert_eq!(solve_b(None, &mut Predictive::default(), None), 10776);
     }
 }
@@ -14,14 +14,22 @@
 // limitations under the License.
 
 
-use std::collections::btree_map::Entry;
-use std::collections::BTreeMap;
+use console::Term;
 
+use mbp_aoc2019::direction::Direction;
+use mbp_aoc2019::explore::{explore_bfs, explore_dfs, Explored, Oracle};
 use mbp_aoc2019::intcode::Computer;
+use mbp_aoc2019::shortest_path::bfs_path;
+use mbp_aoc2019::{point, Point};
 
+/// Run with `--draw` to watch the map being explored, and `--depth-first`
+/// to explore by walking one droid around rather than cloning it.
 pub fn main() {
-    println!("15a: {}", solve_a());
-    println!("15b: {}", solve_b());
+    let args: Vec<String> = std::env::args().collect();
+    let flag = |name: &str| args.iter().any(|a| a == name);
+    let map = Map::explore(flag("--depth-first"), flag("--draw"));
+    println!("15a: {}", map.oxygen_depth);
+    println!("15b: {}", map.flood_oxygen());
 }
 
 #[derive(Eq, PartialEq, Clone, Debug, Copy)]
@@ -51,41 +59,27 @@ impl Square {
     }
 }
 
-#[derive(Eq, PartialEq, Clone, Debug)]
-enum Move {
-    North,
-    South,
-    West,
-    East,
+/// The repair droid, driven by its Intcode program.
+#[derive(Clone)]
+struct Droid {
+    cpu: Computer,
 }
-use Move::*;
 
-impl Move {
-    fn all_moves() -> &'static [Move] {
-        &[North, South, West, East]
-    }
+impl Oracle for Droid {
+    type Cell = Square;
 
-    fn command(&self) -> isize {
-        match self {
-            North => 1,
-            South => 2,
-            West => 3,
-            East => 4,
-        }
+    fn try_move(&mut self, dir: Direction) -> Square {
+        self.cpu.push_input(dir.command());
+        self.cpu.run();
+        assert_eq!(self.cpu.output_len(), 1);
+        Square::from_output(self.cpu.pop_output().unwrap())
     }
 
-    fn move_from(&self, p: &Point) -> Point {
-        match self {
-            North => (p.0, p.1 - 1),
-            South => (p.0, p.1 + 1),
-            West => (p.0 - 1, p.1),
-            East => (p.0 + 1, p.1),
-        }
+    fn is_open(&self, sq: &Square) -> bool {
+        *sq != Wall
     }
 }
 
-type Point = (isize, isize);
-
 fn solve_a() -> usize {
     Map::load().oxygen_depth
 }
@@ -95,130 +89,75 @@ fn solve_b() -> usize {
 }
 
 struct Map {
-    seen: BTreeMap<Point, Square>,
+    explored: Explored<Square>,
     oxygen_depth: usize,
     oxygen_pos: Point,
 }
 
 impl Map {
     fn load() -> Map {
-        // Naively, we'd want to do a breadth-first search from the starting point
-        // until we find the oxygen.
-        //
-        // This is slightly complicated by not being able to jump to any arbitrary
-        // point - except, we could store the computer state and jump there.
-        //
-        // We need to simultaneously discover the contents of the squares,
-        // which squares are reachable, and also the length of the shorest path
-        // to get there...
-        //
-        // By looking at all squares N positions away, before looking any deeper,
-        // we should find the shortest path first.
-        let orig_c = Computer::from_file("input/input15.txt");
-
-        // Contents of all the squares we've seen.
-        let mut seen: BTreeMap<Point, Square> = BTreeMap::new();
-
-        // Squares we want to visit, preceded by their depth, and including
-        // the computer state when in them.
-        let mut queue: Vec<(usize, Point, Computer)> = Vec::new();
-        queue.push((0, (0, 0), orig_c));
-
-        let mut oxygen_depth = None;
-        let mut oxygen_pos = None;
-
-        while !queue.is_empty() {
-            queue.sort_by_key(|a| a.0);
-            // println!("queue len {}", queue.len());
-            let (depth, oldpos, cpu) = queue.remove(0);
-
-            // Try to visit every square neighboring `oldpos`, starting from `cpu`.
-            for m in Move::all_moves() {
-                let newpos = m.move_from(&oldpos);
-                if seen.contains_key(&newpos) {
-                    // println!("have already seen {:?}, skipping", &newpos);
-                    continue;
-                }
-
-                let mut newcpu = cpu.clone();
-                newcpu.push_input(m.command());
-                newcpu.run();
-                assert_eq!(newcpu.output_len(), 1);
-                let sq = Square::from_output(newcpu.pop_output().unwrap());
-                // println!(".. result: {:?}", sq);
-
-                // println!( "move {:?} from {:?} to {:?} at depth {} => {:?}", m, oldpos, newpos, depth, sq);
-                match sq {
-                    Oxygen => {
-                        // println!("found oxygen at {:?}", newpos);
-                        oxygen_depth = Some(depth + 1);
-                        oxygen_pos = Some(newpos);
-                    }
-                    Wall => {}
-                    Empty => {
-                        let newdepth = depth + 1;
-                        queue.push((newdepth, newpos, newcpu));
-                    }
-                }
-
-                seen.insert(newpos, sq);
-            }
-        }
-        let map = Map {
-            seen,
-            oxygen_depth: oxygen_depth.unwrap(),
-            oxygen_pos: oxygen_pos.unwrap(),
-        };
-        println!("{}", map.draw(&(0, 0)));
-        map
+        Map::explore(false, false)
     }
 
-    fn draw(&self, pos: &Point) -> String {
-        if self.seen.is_empty() {
-            return String::new();
+    /// Explore the whole map, either depth-first by walking one droid
+    /// around, or breadth-first by cloning the droid at every square.
+    /// Optionally, draw the map on the terminal as it's discovered.
+    fn explore(depth_first: bool, draw: bool) -> Map {
+        let term = &mut Term::stdout();
+        if draw {
+            term.clear_screen().unwrap();
         }
-        let xmin = self.seen.keys().map(|p| p.0).min().unwrap();
-        let xmax = self.seen.keys().map(|p| p.0).max().unwrap();
-        let ymin = self.seen.keys().map(|p| p.1).min().unwrap();
-        let ymax = self.seen.keys().map(|p| p.1).max().unwrap();
-        let mut s = String::new();
-        for y in ymin..=ymax {
-            for x in xmin..=xmax {
-                if *pos == (x, y) {
-                    s.push('@')
-                } else if let Some(sq) = self.seen.get(&(x, y)) {
-                    s.push(sq.to_char());
-                } else {
-                    s.push(' ')
-                }
+        let on_progress = |explored: &Explored<Square>, pos: Point| {
+            if draw {
+                term.move_cursor_to(0, 0).unwrap();
+                term.write_str(&explored.draw(pos, |sq| sq.to_char()))
+                    .unwrap();
             }
-            s.push('\n');
+        };
+        let mut droid = Droid {
+            cpu: Computer::from_file("input/input15.txt"),
+        };
+        let explored = if depth_first {
+            explore_dfs(&mut droid, Empty, on_progress)
+        } else {
+            explore_bfs(droid, Empty, on_progress)
+        };
+        let oxygen_pos = explored.find(|sq| *sq == Oxygen).unwrap();
+        let oxygen_depth = explored.distances_from(point(0, 0))[&oxygen_pos];
+        Map {
+            explored,
+            oxygen_depth,
+            oxygen_pos,
         }
-        s
     }
 
-    fn flood_oxygen(&mut self) -> usize {
-        // Locations of as-yet-incomplete oxygen.
-        let mut oxy = vec![self.oxygen_pos];
-        for i in 0.. {
-            let mut new_oxy = Vec::new();
-            for oxypos in oxy.into_iter() {
-                for m in Move::all_moves() {
-                    let qxy = m.move_from(&oxypos);
-                    if let Entry::Occupied(mut o) = self.seen.entry(qxy) {
-                        if *o.get() == Empty {
-                            new_oxy.push(qxy);
-                            o.insert(Oxygen);
-                        }
-                    }
-                }
-            }
-            if new_oxy.is_empty() {
-                return i;
-            }
-            oxy = new_oxy;
-        }
-        unreachable!()
+    /// The moves for the droid to take from its start to the oxygen
+    /// system, by one of the shortest routes.
+    fn route_to_oxygen(&self) -> Vec<Direction> {
+        let path = bfs_path(point(0, 0), self.oxygen_pos, &mut |p: Point| {
+            Direction::ALL
+                .iter()
+                .map(|&d| p + d)
+                .filter(|np| self.explored.open.contains(np))
+                .collect()
+        })
+        .unwrap();
+        path.positions()
+            .windows(2)
+            .map(|w| *Direction::ALL.iter().find(|&&d| w[0] + d == w[1]).unwrap())
+            .collect()
+    }
+
+    /// Minutes for oxygen to spread from the oxygen system to every
+    /// reachable square: the distance to the furthest.
+    fn flood_oxygen(&self) -> usize {
+        let (m, origin) = self.explored.to_matrix(Wall);
+        m.flood_fill(self.oxygen_pos + origin, |sq| *sq != Wall)
+            .values()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap()
     }
 }
 
@@ -244,4 +183,25 @@ mod test {
     fn solution_b() {
         assert_eq!(solve_b(), 320);
     }
+
+    #[test]
+    fn replay_route() {
+        let map = Map::load();
+        let route = map.route_to_oxygen();
+        assert_eq!(route.len(), map.oxygen_depth);
+        let mut droid = Droid {
+            cpu: Computer::from_file("input/input15.txt"),
+        };
+        let last = route.iter().map(|&d| droid.try_move(d)).last();
+        assert_eq!(last, Some(Oxygen));
+    }
+
+    #[test]
+    fn depth_first_matches() {
+        let dfs = Map::explore(true, false);
+        let bfs = Map::explore(false, false);
+        assert_eq!(dfs.explored.cells, bfs.explored.cells);
+        assert_eq!(dfs.oxygen_depth, 232);
+        assert_eq!(dfs.flood_oxygen(), 320);
+    }
 }
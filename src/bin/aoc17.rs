@@ -13,15 +13,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-
 use std::convert::TryFrom;
+use std::fmt;
+
+use console::Term;
 
+use mbp_aoc2019::direction::{Direction, Turtle};
 use mbp_aoc2019::intcode::Computer;
 use mbp_aoc2019::Matrix;
 
+/// Longest allowed encoding of the main routine or a movement function.
+const MAX_ROUTINE_LEN: usize = 20;
+
+/// Number of movement functions: A, B and C.
+const MAX_FUNCTIONS: usize = 3;
+
+/// Sent for movement functions that the main routine doesn't call, since
+/// the robot always asks for all three.
+const UNUSED_FUNCTION: &str = "L";
+
+/// Run with `--video` to watch the robot move.
 pub fn main() {
+    let video = std::env::args().any(|a| a == "--video");
     println!("17a: {}", solve_a());
-    println!("17b: {}", solve_b());
+    println!("17b: {}", solve_b(video));
 }
 
 fn is_scaffold(ch: Option<char>) -> bool {
@@ -57,15 +72,201 @@ fn solve_a() -> isize {
     align
 }
 
-fn solve_b() -> isize {
+fn solve_b(video: bool) -> isize {
+    let mut cpu = start_robot(video);
+    if !video {
+        return cpu.interact().unwrap_or(0);
+    }
+    let term = &mut Term::stdout();
+    term.clear_screen().unwrap();
+    watch_video(&mut cpu, |frame| {
+        term.move_cursor_to(0, 0).unwrap();
+        term.write_str(frame).unwrap();
+    })
+}
+
+/// Wake up the robot and give it the routines to follow the whole
+/// scaffold, and say whether to turn on the video feed.
+fn start_robot(video: bool) -> Computer {
+    let path = scaffold_path(&load_map());
+    let routines = compress(&path).expect("can't compress path into routines");
     let mut cpu = Computer::from_file("input/input17.txt");
     cpu.poke_at(0, 2);
-    cpu.push_input_string("A,B,A,C,B,C,B,C,A,C\n");
-    cpu.push_input_string("L,10,R,12,R,12\n");
-    cpu.push_input_string("R,6,R,10,L,10\n");
-    cpu.push_input_string("R,10,L,10,L,12,R,6\n");
-    cpu.push_input_string("1\n");
-    cpu.interact().unwrap_or(0)
+    cpu.push_input_string(&routines.main_text());
+    cpu.push_input_string("\n");
+    for line in routines.function_lines() {
+        cpu.push_input_string(&line);
+        cpu.push_input_string("\n");
+    }
+    cpu.push_input_string(if video { "y\n" } else { "n\n" });
+    cpu
+}
+
+/// Run the robot with its video feed on, passing every frame to
+/// `on_frame`, and return the amount of dust it reports at the end.
+///
+/// Frames are separated by blank lines, which aren't included. Most are the
+/// whole map, drawn after every move, but one is the robot's prompts.
+fn watch_video<F: FnMut(&str)>(cpu: &mut Computer, mut on_frame: F) -> isize {
+    let mut frame = String::new();
+    while let Some(c) = cpu.run_until_output() {
+        if c > 127 {
+            // The amount of dust is the last output.
+            return c;
+        }
+        frame.push(char::try_from(c as u32).unwrap());
+        if frame.ends_with("\n\n") {
+            frame.pop();
+            on_frame(&frame);
+            frame.clear();
+        }
+    }
+    panic!("robot stopped without reporting dust");
+}
+
+/// One leg of the robot's route: turns, as they'd be written in a
+/// routine, followed by some steps forward.
+///
+/// Only the first leg can go straight on without turning, or turn around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Leg {
+    turns: &'static str,
+    steps: usize,
+}
+
+impl fmt::Display for Leg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.turns.is_empty() {
+            write!(f, "{}", self.steps)
+        } else {
+            write!(f, "{},{}", self.turns, self.steps)
+        }
+    }
+}
+
+fn legs_text(legs: &[Leg]) -> String {
+    legs.iter()
+        .map(Leg::to_string)
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Follow the scaffold from the robot, going straight across intersections
+/// and turning only at corners, until it reaches the far end.
+fn scaffold_path(mat: &Matrix<char>) -> Vec<Leg> {
+    let pos = mat
+        .iter_points()
+        .find(|p| Direction::from_arrow(mat[*p]).is_some())
+        .expect("no robot on the map");
+    let mut turtle = Turtle::new(pos, Direction::from_arrow(mat[pos]).unwrap());
+    let mut legs = Vec::new();
+    loop {
+        let behind = turtle.pos + turtle.heading.reverse();
+        let turns = if legs.is_empty() && is_scaffold(mat.try_get(turtle.ahead())) {
+            ""
+        } else if is_scaffold(mat.try_get(turtle.left())) {
+            turtle.turn_left();
+            "L"
+        } else if is_scaffold(mat.try_get(turtle.right())) {
+            turtle.turn_right();
+            "R"
+        } else if legs.is_empty() && is_scaffold(mat.try_get(behind)) {
+            turtle.turn_right();
+            turtle.turn_right();
+            "R,R"
+        } else {
+            assert!(!legs.is_empty(), "robot isn't on any scaffold");
+            return legs;
+        };
+        let mut steps = 0;
+        while is_scaffold(mat.try_get(turtle.ahead())) {
+            turtle.forward();
+            steps += 1;
+        }
+        legs.push(Leg { turns, steps });
+    }
+}
+
+/// A main routine, as indexes into the movement functions that together
+/// cover the whole path.
+#[derive(Debug)]
+struct Routines {
+    main: Vec<usize>,
+    functions: Vec<Vec<Leg>>,
+}
+
+impl Routines {
+    fn main_text(&self) -> String {
+        self.main
+            .iter()
+            .map(|&i| ((b'A' + i as u8) as char).to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    /// The text of every movement function the robot asks for, with a
+    /// placeholder for those that aren't needed.
+    fn function_lines(&self) -> Vec<String> {
+        (0..MAX_FUNCTIONS)
+            .map(|i| match self.functions.get(i) {
+                Some(f) => legs_text(f),
+                None => UNUSED_FUNCTION.to_owned(),
+            })
+            .collect()
+    }
+}
+
+/// Split the path into a main routine calling up to three movement
+/// functions, each fitting within the robot's memory limits.
+///
+/// The path must start with function A, so try every prefix that's short
+/// enough; then after each call, either reuse a function that matches what
+/// comes next, or define a new one from some prefix of the remainder.
+fn compress(path: &[Leg]) -> Option<Routines> {
+    let mut r = Routines {
+        main: Vec::new(),
+        functions: Vec::new(),
+    };
+    if compress_from(path, &mut r) {
+        Some(r)
+    } else {
+        None
+    }
+}
+
+fn compress_from(rest: &[Leg], r: &mut Routines) -> bool {
+    if rest.is_empty() {
+        return true;
+    }
+    // Each call takes one letter plus a comma separator.
+    if (r.main.len() + 1) * 2 - 1 > MAX_ROUTINE_LEN {
+        return false;
+    }
+    for i in 0..r.functions.len() {
+        let len = r.functions[i].len();
+        if rest.starts_with(&r.functions[i]) {
+            r.main.push(i);
+            if compress_from(&rest[len..], r) {
+                return true;
+            }
+            r.main.pop();
+        }
+    }
+    if r.functions.len() < MAX_FUNCTIONS {
+        for len in 1..=rest.len() {
+            if legs_text(&rest[..len]).len() > MAX_ROUTINE_LEN {
+                break;
+            }
+            r.functions.push(rest[..len].to_vec());
+            r.main.push(r.functions.len() - 1);
+            if compress_from(&rest[len..], r) {
+                return true;
+            }
+            r.main.pop();
+            r.functions.pop();
+        }
+    }
+    false
 }
 
 fn load_map() -> Matrix<char> {
@@ -91,6 +292,75 @@ mod test {
 
     #[test]
     fn solution_b() {
-        assert_eq!(solve_b(), 927_809);
+        assert_eq!(solve_b(false), 927_809);
+    }
+
+    #[test]
+    fn path_and_routines() {
+        let path = scaffold_path(&load_map());
+        assert_eq!(
+            legs_text(&path),
+            "L,10,R,12,R,12,R,6,R,10,L,10,L,10,R,12,R,12,R,10,L,10,L,12,R,6,\
+             R,6,R,10,L,10,R,10,L,10,L,12,R,6,R,6,R,10,L,10,R,10,L,10,L,12,R,6,\
+             L,10,R,12,R,12,R,10,L,10,L,12,R,6"
+        );
+
+        let r = compress(&path).unwrap();
+        assert_eq!(r.main_text(), "A,B,A,C,B,C,B,C,A,C");
+        assert_eq!(legs_text(&r.functions[0]), "L,10,R,12,R,12");
+        assert_eq!(legs_text(&r.functions[1]), "R,6,R,10,L,10");
+        assert_eq!(legs_text(&r.functions[2]), "R,10,L,10,L,12,R,6");
+        let expanded: Vec<Leg> = r
+            .main
+            .iter()
+            .flat_map(|&i| r.functions[i].iter().cloned())
+            .collect();
+        assert_eq!(expanded, path);
+    }
+
+    #[test]
+    fn unused_functions() {
+        let leg = Leg {
+            turns: "L",
+            steps: 4,
+        };
+        let r = compress(&[leg, leg, leg]).unwrap();
+        assert_eq!(r.main_text(), "A,A,A");
+        assert_eq!(r.function_lines(), vec!["L,4", "L", "L"]);
+    }
+
+    /// The robot might already face along the scaffold, or face away from
+    /// it.
+    #[test]
+    fn first_leg_without_a_turn() {
+        let path = |map: &str| legs_text(&scaffold_path(&Matrix::from_string_lines(map)));
+        assert_eq!(path("..#\n>##\n"), "2,L,1");
+        assert_eq!(path("<###\n...#\n"), "R,R,3,R,1");
+        assert_eq!(path("^###\n"), "R,3");
+
+        let straight = scaffold_path(&Matrix::from_string_lines(">###\n"));
+        assert_eq!(compress(&straight).unwrap().main_text(), "A");
+    }
+
+    #[test]
+    #[should_panic(expected = "robot isn't on any scaffold")]
+    fn robot_off_the_scaffold() {
+        scaffold_path(&Matrix::from_string_lines(".^.\n"));
+    }
+
+    #[test]
+    fn video_frames() {
+        let height = load_map().height();
+        let mut frames = Vec::new();
+        let dust = watch_video(&mut start_robot(true), |f| frames.push(f.to_owned()));
+        assert_eq!(dust, 927_809);
+        assert!(frames.len() > 100);
+        assert!(frames[1].starts_with("Main:\n"));
+        for (i, frame) in frames.iter().enumerate() {
+            if i != 1 {
+                assert_eq!(frame.lines().count(), height, "frame {}", i);
+                assert!(frame.ends_with('\n') && !frame.ends_with("\n\n"));
+            }
+        }
     }
 }
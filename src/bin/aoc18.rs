@@ -18,6 +18,8 @@ use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
 use std::fmt;
 
+use mbp_aoc2019::maze_graph::{contract, MazeGraph};
+use mbp_aoc2019::shortest_path::{bfs_distances, DistanceCache};
 use mbp_aoc2019::{Matrix, Point};
 
 type Map = Matrix<char>;
@@ -31,7 +33,7 @@ pub fn main() {
     println!("18b: {}", solve_b());
 }
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
 struct KeySet {
     u: u32,
 }
@@ -63,6 +65,10 @@ impl KeySet {
         self.u &= !other.u;
     }
 
+    fn union(&mut self, other: &KeySet) {
+        self.u |= other.u;
+    }
+
     fn is_empty(&self) -> bool {
         self.u == 0
     }
@@ -119,19 +125,10 @@ fn solve_b() -> usize {
 
 fn solve_type_a(s: &str) -> usize {
     let mat = Matrix::from_string_lines(s);
-    let mut all_keys = KeySet::new();
-    let mut key_pos: BTreeMap<char, Point> = BTreeMap::new();
-    for p in mat.iter_points() {
-        let c = mat[p];
-        if c.is_ascii_lowercase() {
-            all_keys.set(c);
-            key_pos.insert(c, p);
-        } else if c == PLAYER {
-            key_pos.insert(c, p);
-        }
-    }
-    let start = *key_pos.get(&'@').unwrap();
-    let n_keys = all_keys.len();
+    let start = mat.iter_points().find(|p| mat[*p] == PLAYER).unwrap();
+    let n_keys = reachable_keys(&mat, start).len();
+    let graph = key_graph(&mat);
+    let mut cache = key_distances(&graph);
 
     // Best seen distance to collect all keys.
     let mut best_overall: usize = std::usize::MAX;
@@ -143,12 +140,12 @@ fn solve_type_a(s: &str) -> usize {
     for gen in 1..=n_keys {
         let mut next_queue: BTreeMap<(KeySet, Point), usize> = BTreeMap::new();
         for ((ks0, p0), dist0) in queue.into_iter() {
-            for (dist1, ks1, _c1, p1) in reachable(&mat, dist0, &ks0, p0, &all_keys) {
+            for (dist1, ks1, _c1, p1) in reachable(&mut cache, &graph, dist0, &ks0, p0) {
                 debug_assert_eq!(gen, ks1.len());
                 if ks1.len() == n_keys {
                     best_overall = std::cmp::min(best_overall, dist1);
                 }
-                let newk = (ks1.clone(), p1);
+                let newk = (ks1, p1);
                 match next_queue.entry(newk) {
                     Entry::Vacant(v) => {
                         v.insert(dist1);
@@ -167,20 +164,8 @@ fn solve_type_a(s: &str) -> usize {
 
 fn solve_type_b(s: &str) -> usize {
     let mut mat = Matrix::from_string_lines(s);
-    let mut all_keys = KeySet::new();
-    let mut key_pos: BTreeMap<char, Point> = BTreeMap::new();
-    for p in mat.iter_points() {
-        let c = mat[p];
-        if c.is_ascii_lowercase() {
-            all_keys.set(c);
-            key_pos.insert(c, p);
-        } else if c == PLAYER {
-            key_pos.insert(c, p);
-        }
-    }
-    let start = *key_pos.get(&'@').unwrap();
+    let start = mat.iter_points().find(|p| mat[*p] == PLAYER).unwrap();
     edit_for_b(&mut mat, start);
-    let n_keys = all_keys.len();
 
     // Best seen distance to collect all keys.
     let mut best_overall: usize = std::usize::MAX;
@@ -194,19 +179,36 @@ fn solve_type_b(s: &str) -> usize {
         start.left().down(),
         start.right().down(),
     ];
+    for &p in &starts {
+        mat[p] = PLAYER;
+    }
+    // Keys each robot can ever get to, once the doors are open.
+    let robot_keys: Vec<KeySet> = starts.iter().map(|&p| reachable_keys(&mat, p)).collect();
+    let n_keys = robot_keys
+        .iter()
+        .fold(KeySet::new(), |mut all, ks| {
+            all.union(ks);
+            all
+        })
+        .len();
+    let graph = key_graph(&mat);
+    let mut cache = key_distances(&graph);
     queue.insert((KeySet::new(), starts), 0);
     for gen in 1..=n_keys {
         let mut next_queue = BTreeMap::new();
         for ((ks0, robots), dist0) in queue.into_iter() {
             for (i, &p0) in robots.iter().enumerate() {
-                for (dist1, ks1, _c1, p1) in reachable(&mat, dist0, &ks0, p0, &all_keys) {
+                if robot_keys[i].is_subset_of(&ks0) {
+                    continue;
+                }
+                for (dist1, ks1, _c1, p1) in reachable(&mut cache, &graph, dist0, &ks0, p0) {
                     debug_assert_eq!(gen, ks1.len());
                     if ks1.len() == n_keys {
                         best_overall = std::cmp::min(best_overall, dist1);
                     }
                     let mut newbots = robots;
                     newbots[i] = p1;
-                    let newk = (ks1.clone(), newbots);
+                    let newk = (ks1, newbots);
                     match next_queue.entry(newk) {
                         Entry::Vacant(v) => {
                             v.insert(dist1);
@@ -233,127 +235,270 @@ fn edit_for_b(mat: &mut Map, start: Point) {
     // We don't bother for now adding the individual markers.
 }
 
-/// Return a vec of (distance, keyset, key) for every new key reachable from
-/// p given current KeySet.
-fn reachable(
-    mat: &Map,
+/// Every key in the same part of the maze as `start`, ignoring doors.
+fn reachable_keys(mat: &Map, start: Point) -> KeySet {
+    let open = bfs_distances(Some(start), &mut |p: Point| {
+        mat.neighbors4(p)
+            .into_iter()
+            .filter(|(_, c)| **c != WALL)
+            .map(|(np, _)| np)
+            .collect()
+    });
+    let mut keys = KeySet::new();
+    for p in open.keys() {
+        if mat[*p].is_ascii_lowercase() {
+            keys.set(mat[*p]);
+        }
+    }
+    keys
+}
+
+/// Contract the maze into a graph between the keys and the starting points,
+/// with doors on the edges.
+fn key_graph(mat: &Map) -> MazeGraph {
+    contract(
+        mat,
+        |c| c != WALL,
+        |_, c| c == PLAYER || c.is_ascii_lowercase(),
+        |c| c.is_ascii_uppercase(),
+    )
+}
+
+/// A node in the key graph, and every key needed to get there along some
+/// route: the keys for doors on the way, and the keys passed over,
+/// including the one at the node itself. Then, just the keys passed over.
+type Route = (Point, KeySet, KeySet);
+
+/// Distances along routes from each node to every other node, searched the
+/// first time they're asked for and then remembered.
+///
+/// Routes don't depend on which keys are held, so one search from each
+/// node can be reused for any set of keys that includes what a route needs.
+/// A route never passes over the same key twice, since that can't make it
+/// any shorter or need fewer keys.
+fn key_distances(
+    graph: &MazeGraph,
+) -> DistanceCache<Route, usize, impl FnMut(Route) -> Vec<(Route, usize)> + '_> {
+    DistanceCache::new(move |(p, needs, passed): Route| {
+        graph
+            .edges_from(p)
+            .iter()
+            .filter_map(|edge| {
+                let c = graph.nodes[&edge.to];
+                if c.is_ascii_lowercase() && passed.contains(c) {
+                    return None;
+                }
+                let (mut needs, mut passed) = (needs, passed);
+                for &door in &edge.doors {
+                    needs.set(door.to_ascii_lowercase());
+                }
+                if c.is_ascii_lowercase() {
+                    needs.set(c);
+                    passed.set(c);
+                }
+                Some(((edge.to, needs, passed), edge.dist))
+            })
+            .collect()
+    })
+}
+
+/// Return a vec of (distance, keyset, key, position) for every new key
+/// reachable from p given current KeySet.
+///
+/// Paths can go through doors we have the key for, and through keys we
+/// already have, but stop at the first new key.
+fn reachable<N>(
+    cache: &mut DistanceCache<Route, usize, N>,
+    graph: &MazeGraph,
     dist0: usize,
     ks: &KeySet,
     p: Point,
-    all_keys: &KeySet,
-) -> Vec<(usize, KeySet, char, Point)> {
-    let mut queue = vec![p];
-    let mut seen = Matrix::new(mat.width(), mat.height(), false);
-    let mut dist = dist0 + 1;
-    let mut result = Vec::new();
-    let mut remaining_keys = all_keys.clone();
-    remaining_keys.subtract(&ks);
-    while !queue.is_empty() && !remaining_keys.is_empty() {
-        // Find every as-yet-unvisited neighbor at this distance.
-        let mut new_queue: Vec<Point> = Vec::new();
-        for p1 in queue.into_iter() {
-            for (p2, &c) in mat.neighbors4(p1) {
-                if c == WALL || seen[p2] {
-                    continue;
-                }
-                seen[p2] = true;
-                if c == PASSAGE || c == PLAYER || ks.contains(c) {
-                    // Either empty, or we've already collected this key, or we have
-                    // the key for this door.
-                    new_queue.push(p2);
-                } else if c.is_ascii_lowercase() {
-                    // Found a new key. Take this; can't go further.
-                    let mut ks1 = ks.clone();
-                    ks1.set(c);
-                    result.push((dist, ks1, c, p2));
-                    remaining_keys.clear(c);
-                } else {
-                    // A door for which we don't have the key.
-                    debug_assert!(c.is_ascii_uppercase());
-                }
-            }
+) -> Vec<(usize, KeySet, char, Point)>
+where
+    N: FnMut(Route) -> Vec<(Route, usize)>,
+{
+    let mut start = KeySet::new();
+    let c = graph.nodes[&p];
+    if c.is_ascii_lowercase() {
+        start.set(c);
+    }
+    let mut best: BTreeMap<Point, usize> = BTreeMap::new();
+    for (&(p1, needs, _), &dist) in cache.distances_from((p, start, start)) {
+        let c = graph.nodes[&p1];
+        if !c.is_ascii_lowercase() || ks.contains(c) {
+            continue;
+        }
+        let mut needs = needs;
+        needs.clear(c);
+        if needs.is_subset_of(ks) {
+            let d = best.entry(p1).or_insert(dist);
+            *d = std::cmp::min(*d, dist);
         }
-        queue = new_queue;
-        dist += 1;
     }
-    result
+    best.into_iter()
+        .map(|(p1, dist)| {
+            let c = graph.nodes[&p1];
+            let mut ks1 = *ks;
+            ks1.set(c);
+            (dist0 + dist, ks1, c, p1)
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use mbp_aoc2019::shortest_path::{
+        bfs_distances, bidirectional_distance, ida_star, shortest_distance,
+    };
 
     #[test]
     fn solution_a() {
         assert_eq!(solve_a(), 4204);
     }
 
+    fn example(n: usize) -> String {
+        std::fs::read_to_string(format!("input/example_18_{}.txt", n)).unwrap()
+    }
+
     #[test]
     fn example_1() {
-        assert_eq!(
-            solve_type_a(
-                "\
-########################
-#f.D.E.e.C.b.A.@.a.B.c.#
-######################.#
-#d.....................#
-########################
-"
-            ),
-            86
-        );
+        assert_eq!(solve_type_a(&example(1)), 86);
     }
 
     #[test]
     fn example_2() {
-        assert_eq!(
-            solve_type_a(
-                "\
-########################
-#...............b.C.D.f#
-#.######################
-#.....@.a.B.c.d.A.e.F.g#
-########################
-"
-            ),
-            132
-        );
+        assert_eq!(solve_type_a(&example(2)), 132);
     }
 
     #[test]
     fn example_3() {
-        assert_eq!(
-            solve_type_a(
-                "\
-#################
-#i.G..c...e..H.p#
-########.########
-#j.A..b...f..D.o#
-########@########
-#k.E..a...g..B.n#
-########.########
-#l.F..d...h..C.m#
-#################
-"
-            ),
-            136
-        );
+        assert_eq!(solve_type_a(&example(3)), 136);
     }
 
     #[test]
     fn example_4() {
+        assert_eq!(solve_type_a(&example(4)), 81);
+    }
+
+    /// Collect all the keys by searching the states with IDA*, rather than
+    /// generation by generation.
+    fn solve_ida(s: &str) -> usize {
+        let mat = Matrix::from_string_lines(s);
+        let n_keys = mat.values().filter(|c| c.is_ascii_lowercase()).count();
+        let start = mat.iter_points().find(|p| mat[*p] == PLAYER).unwrap();
+        let graph = key_graph(&mat);
+        let mut cache = key_distances(&graph);
+        let mut nbrs = |(p, ks): (Point, KeySet)| {
+            reachable(&mut cache, &graph, 0, &ks, p)
+                .into_iter()
+                .map(|(dist, ks1, _, p1)| ((p1, ks1), dist))
+                .collect()
+        };
+        ida_star(
+            (start, KeySet::new()),
+            |(_, ks)| ks.len() == n_keys,
+            &mut nbrs,
+            // Every missing key is at least one step away.
+            |(_, ks)| n_keys - ks.len(),
+        )
+        .unwrap()
+        .dist
+    }
+
+    #[test]
+    fn ida_star_agrees() {
+        // The third example is left out: it has 16 keys with few doors, so
+        // there are many orders to collect them with equal estimates, and
+        // IDA* takes minutes going over them again at every threshold.
+        for maze in &[example(1), example(2), example(4)] {
+            assert_eq!(solve_ida(maze), solve_type_a(maze));
+        }
+    }
+
+    /// With every key held, the routes to each key are as short as a
+    /// search one square at a time through every door.
+    #[test]
+    fn routes_match_open_maze() {
+        let mat = Matrix::from_string_lines(&example(4));
+        let start = mat.iter_points().find(|p| mat[*p] == PLAYER).unwrap();
+        let open = bfs_distances(Some(start), &mut |p: Point| {
+            mat.neighbors4(p)
+                .into_iter()
+                .filter(|(_, c)| **c != WALL)
+                .map(|(np, _)| np)
+                .collect()
+        });
+        let graph = key_graph(&mat);
+        let mut cache = key_distances(&graph);
+        let mut all_keys = KeySet::new();
+        for c in mat.values().filter(|c| c.is_ascii_lowercase()) {
+            all_keys.set(*c);
+        }
+        let mut ks = all_keys;
+        let (key, key_pos) = graph
+            .nodes
+            .iter()
+            .map(|(p, c)| (*c, *p))
+            .find(|(c, _)| c.is_ascii_lowercase())
+            .unwrap();
+        ks.clear(key);
         assert_eq!(
-            solve_type_a(
-                "\
-########################
-#@..............ac.GI.b#
-###d#e#f################
-###A#B#C################
-###g#h#i################
-########################
-"
-            ),
-            81
+            reachable(&mut cache, &graph, 0, &ks, start),
+            vec![(open[&key_pos], all_keys, key, key_pos)]
         );
+        for (&p, &c) in &graph.nodes {
+            if c.is_ascii_lowercase() {
+                let best = cache
+                    .distances_from((start, KeySet::new(), KeySet::new()))
+                    .iter()
+                    .filter(|((p1, _, _), _)| *p1 == p)
+                    .map(|(_, dist)| *dist)
+                    .min();
+                assert_eq!(best, Some(open[&p]));
+            }
+        }
+    }
+
+    /// A map for part b, already split between four robots.
+    const FOUR_ROBOTS: &str = "\
+#############
+#g#f.D#..h#l#
+#F###e#E###.#
+#dCba@#@BcIJ#
+#############
+#nK.L@#@G...#
+#M###N#H###.#
+#o#m..#i#jk.#
+#############
+";
+
+    /// Bidirectional search only works on an undirected graph, which rules
+    /// out the search over robot positions and keys held, since keys are
+    /// never put back. Instead, check it on the key graph of the four-robot
+    /// map with every door open: each robot's quadrant is a separate part,
+    /// so some nodes can't reach each other.
+    #[test]
+    fn bidirectional_agrees() {
+        let mat = Matrix::from_string_lines(FOUR_ROBOTS);
+        let graph = key_graph(&mat);
+        let mut nbrs = |p: Point| -> Vec<(Point, usize)> {
+            graph
+                .edges_from(p)
+                .iter()
+                .map(|edge| (edge.to, edge.dist))
+                .collect()
+        };
+        let mut unreachable = 0;
+        for &a in graph.nodes.keys() {
+            for &b in graph.nodes.keys() {
+                let expected = shortest_distance(a, b, &mut nbrs);
+                if expected.is_none() {
+                    unreachable += 1;
+                }
+                assert_eq!(bidirectional_distance(a, b, &mut nbrs), expected);
+            }
+        }
+        assert!(unreachable > 0);
     }
 }
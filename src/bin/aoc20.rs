@@ -16,15 +16,20 @@
 
 #![allow(unused_imports, dead_code)]
 
-use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::Path;
 
-use mbp_aoc2019::shortest_path::shortest_distance;
+use mbp_aoc2019::maze_graph::{contract, MazeGraph};
+use mbp_aoc2019::point_n::Point3;
+use mbp_aoc2019::shortest_path::{bounded_search, shortest_distance, shortest_path, Limits};
 use mbp_aoc2019::{point, Matrix, Point};
 
 const PASSAGE: char = '.';
 
+/// Give up searching a recursive maze after looking at this many portals,
+/// since it might have no way out and go down forever.
+const MULTI_LEVEL_LIMIT: usize = 100_000;
+
 type Label = String;
 
 pub fn main() {
@@ -37,7 +42,7 @@ fn solve_a() -> isize {
 }
 
 fn solve_b() -> isize {
-    Maze::from_input_file().multi_level_path()
+    Maze::from_input_file().multi_level_path().unwrap()
 }
 
 /// Describes one level of the possibly-recursive map.
@@ -57,27 +62,8 @@ struct Maze {
     warp_down: HashMap<Point, Point>,
     warp_up: HashMap<Point, Point>,
 
-    /// Memoized same-level neighbors.
-    memo_neighbors: RefCell<HashMap<Point, Vec<Point>>>,
-}
-
-/// A point in 3d-space
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-struct Point3 {
-    /// Depth into the maze, where the entrance and exit are at 0
-    depth: isize,
-    x: isize,
-    y: isize,
-}
-
-impl Point3 {
-    fn at_depth(p: Point, depth: isize) -> Point3 {
-        Point3 {
-            x: p.x,
-            y: p.y,
-            depth,
-        }
-    }
+    /// Corridors between portals on the same level.
+    graph: MazeGraph,
 }
 
 impl Maze {
@@ -112,13 +98,20 @@ impl Maze {
                 warp_down.insert(*p1, *p2);
             }
         }
+        let portals: Vec<Point> = labels.values().flatten().copied().collect();
+        let graph = contract(
+            &matrix,
+            |c| c == PASSAGE,
+            |p, _| portals.contains(&p),
+            |_| false,
+        );
         Maze {
+            graph,
             matrix,
             labels,
             warps,
             warp_down,
             warp_up,
-            memo_neighbors: RefCell::new(HashMap::new()),
         }
     }
 
@@ -144,12 +137,7 @@ impl Maze {
     }
 
     fn entrance3(&self) -> Point3 {
-        let p = self.entrance();
-        Point3 {
-            depth: 0,
-            x: p.x,
-            y: p.y,
-        }
+        Point3::from_point(self.entrance(), 0)
     }
 
     fn exit(&self) -> Point {
@@ -157,40 +145,28 @@ impl Maze {
     }
 
     fn exit3(&self) -> Point3 {
-        let p = self.exit();
-        Point3 {
-            depth: 0,
-            x: p.x,
-            y: p.y,
-        }
+        Point3::from_point(self.exit(), 0)
     }
 
-    /// Return the same-level neighbors, without portals.
-    fn flat_neighbors(&self, p: Point) -> Vec<Point> {
-        self.memo_neighbors
-            .borrow_mut()
-            .entry(p)
-            .or_insert_with(|| {
-                self.matrix
-                    .neighbors4(p)
-                    .into_iter()
-                    .filter(|(_, c1)| **c1 == PASSAGE)
-                    .map(|(p1, _)| p1)
-                    .collect()
-            })
-            .clone()
+    /// Return the other portals that can be reached on the same level,
+    /// and the distance to them.
+    fn flat_neighbors(&self, p: Point) -> Vec<(Point, isize)> {
+        self.graph
+            .edges_from(p)
+            .iter()
+            .map(|edge| (edge.to, edge.dist as isize))
+            .collect()
     }
 
     /// Return the neighbors of point `p` in a single-level maze.
     ///
-    /// `p` must be a passage square.
+    /// `p` must be a portal square.
     ///
-    /// That is: immediately neighboring other passage squares, or if there is a neighboring portal
-    /// that has a twin, you can warp to the square outside its twin. All of these are one step.
+    /// That is: other portals along the passages on the same level, or if this
+    /// portal has a twin, you can warp to the square outside its twin in one step.
     fn single_level_neighbors(&self, p: Point) -> Vec<(Point, isize)> {
         debug_assert_eq!(self.matrix.try_get(p).unwrap(), PASSAGE);
-        let mut n: Vec<(Point, isize)> =
-            self.flat_neighbors(p).into_iter().map(|p| (p, 1)).collect();
+        let mut n = self.flat_neighbors(p);
         if let Some(out_p) = self.warps.get(&p) {
             n.push((*out_p, 1))
         }
@@ -199,25 +175,28 @@ impl Maze {
 
     /// Return the neighbors of point `p` in a multi-level maze.
     ///
-    /// They are: every direct neighbor at the same depth, plus traversal
+    /// They are: every portal reachable at the same depth, plus traversal
     /// downwards through the inner warps, and upward through the outer warps.
+    ///
+    /// The third component of `p3` is the depth into the maze, where the
+    /// entrance and exit are at 0.
     fn multi_level_neighbors(&self, p3: Point3) -> Vec<(Point3, isize)> {
-        let flatp = point(p3.x, p3.y);
-        let depth = p3.depth;
+        let flatp = p3.xy();
+        let depth = p3.z();
         let mut n: Vec<(Point3, isize)> = self
             .flat_neighbors(flatp)
             .into_iter()
-            .map(|p1| (Point3::at_depth(p1, depth), 1))
+            .map(|(p1, dist)| (Point3::from_point(p1, depth), dist))
             .collect();
         // if let Some(out_p) = self.warps.get(&p) { n.push((*out_p, 1)) }
         if depth > 0 {
             if let Some(up) = self.warp_up.get(&flatp) {
-                n.push((Point3::at_depth(*up, depth - 1), 1));
+                n.push((Point3::from_point(*up, depth - 1), 1));
             }
         }
         if let Some(down) = self.warp_down.get(&flatp) {
             // println!("Down to {}", depth + 1);
-            n.push((Point3::at_depth(*down, depth + 1), 1));
+            n.push((Point3::from_point(*down, depth + 1), 1));
         }
         n
     }
@@ -227,13 +206,73 @@ impl Maze {
         shortest_distance(self.entrance(), self.exit(), &mut |p| {
             self.single_level_neighbors(p)
         })
+        .unwrap()
     }
 
-    /// Find the shortest path in a recursive multi-level maze.
-    fn multi_level_path(&self) -> isize {
-        shortest_distance(self.entrance3(), self.exit3(), &mut |p3| {
-            self.multi_level_neighbors(p3)
+    /// The labels of the portals passed through on the shortest path from AA
+    /// to ZZ in a single-level maze, including both ends.
+    fn single_level_route(&self) -> Vec<Label> {
+        let path = shortest_path(self.entrance(), self.exit(), &mut |p| {
+            self.single_level_neighbors(p)
         })
+        .unwrap();
+        let mut route: Vec<Label> = path
+            .positions()
+            .iter()
+            .map(|p| self.label_at(*p).unwrap().clone())
+            .collect();
+        // Warping gives the same label twice in a row.
+        route.dedup();
+        route
+    }
+
+    fn label_at(&self, p: Point) -> Option<&Label> {
+        self.labels
+            .iter()
+            .find(|(_, points)| points.contains(&p))
+            .map(|(label, _)| label)
+    }
+
+    /// Find the shortest path in a recursive multi-level maze, or None if
+    /// none is found within `MULTI_LEVEL_LIMIT`.
+    fn multi_level_path(&self) -> Option<isize> {
+        let exit = self.exit3();
+        bounded_search(
+            self.entrance3(),
+            |&p3| p3 == exit,
+            &mut |p3| self.multi_level_neighbors(p3),
+            self.depth_heuristic(),
+            Limits {
+                max_dist: None,
+                max_expanded: Some(MULTI_LEVEL_LIMIT),
+            },
+        )
+        .path
+        .map(|path| path.dist)
+    }
+
+    /// An admissible A* heuristic for the multi-level maze.
+    ///
+    /// Getting back to the top level from depth `d` takes `d` outer warps,
+    /// of one step each, and in between them walking at least the shortest
+    /// corridor from an inner portal to an outer one.
+    fn depth_heuristic(&self) -> impl Fn(&Point3) -> isize {
+        let shortest = self
+            .graph
+            .edges
+            .values()
+            .flatten()
+            .map(|edge| edge.dist as isize)
+            .min()
+            .unwrap_or(0);
+        move |p3| {
+            let depth = p3.z();
+            if depth > 0 {
+                depth + (depth - 1) * shortest
+            } else {
+                0
+            }
+        }
     }
 }
 
@@ -317,6 +356,7 @@ mod test {
         assert_eq!(*map.warps.get(&point(2, 8)).unwrap(), point(9, 6));
         assert_eq!(*map.warps.get(&point(9, 6)).unwrap(), point(2, 8));
         assert_eq!(map.single_level_path(), 23);
+        assert_eq!(map.single_level_route(), vec!["AA", "BC", "DE", "FG", "ZZ"]);
     }
 
     #[test]
@@ -333,13 +373,93 @@ mod test {
     #[test]
     fn example_b_1() {
         let map = Maze::from_file("input/example_20_1.txt");
-        assert_eq!(map.multi_level_path(), 26);
+        assert_eq!(map.multi_level_path(), Some(26));
     }
 
     #[test]
     fn example_b_3() {
         let map = Maze::from_file("input/example_20_3.txt");
-        assert_eq!(map.multi_level_path(), 396);
+        assert_eq!(map.multi_level_path(), Some(396));
+    }
+
+    #[test]
+    fn astar_expands_less() {
+        let (mut total, mut total_dijkstra) = (0, 0);
+        for (path, dist) in &[
+            ("input/example_20_1.txt", 26),
+            ("input/example_20_3.txt", 396),
+        ] {
+            let map = Maze::from_file(path);
+            let exit = map.exit3();
+            let search = |heuristic: &dyn Fn(&Point3) -> isize| {
+                bounded_search(
+                    map.entrance3(),
+                    |&p3| p3 == exit,
+                    &mut |p3| map.multi_level_neighbors(p3),
+                    heuristic,
+                    Limits::default(),
+                )
+            };
+            let dijkstra = search(&|_| 0);
+            let astar = search(&map.depth_heuristic());
+            assert_eq!(dijkstra.path.unwrap().dist, *dist);
+            assert_eq!(astar.path.unwrap().dist, *dist);
+            assert!(astar.stats.expanded <= dijkstra.stats.expanded);
+            total += astar.stats.expanded;
+            total_dijkstra += dijkstra.stats.expanded;
+        }
+        assert!(total < total_dijkstra);
+    }
+
+    #[test]
+    fn other_strategies_agree() {
+        use mbp_aoc2019::shortest_path::{bidirectional_distance, ida_star};
+
+        for (path, single, multi) in &[
+            ("input/example_20_1.txt", 23, Some(26)),
+            ("input/example_20_2.txt", 58, None),
+            ("input/example_20_3.txt", 77, Some(396)),
+        ] {
+            let map = Maze::from_file(path);
+            let mut single_nbrs = |p| map.single_level_neighbors(p);
+            assert_eq!(
+                bidirectional_distance(map.entrance(), map.exit(), &mut single_nbrs),
+                Some(*single)
+            );
+            let ida = ida_star(
+                map.entrance(),
+                |&p| p == map.exit(),
+                &mut single_nbrs,
+                |_| 0,
+            );
+            assert_eq!(ida.unwrap().dist, *single);
+
+            if let Some(multi) = multi {
+                let mut multi_nbrs = |p3| map.multi_level_neighbors(p3);
+                assert_eq!(
+                    bidirectional_distance(map.entrance3(), map.exit3(), &mut multi_nbrs),
+                    Some(*multi)
+                );
+            }
+        }
+
+        // IDA* looks at the same states over and over, so only try it on the
+        // small recursive maze.
+        let map = Maze::from_file("input/example_20_1.txt");
+        let exit = map.exit3();
+        let ida = ida_star(
+            map.entrance3(),
+            |&p3| p3 == exit,
+            &mut |p3| map.multi_level_neighbors(p3),
+            map.depth_heuristic(),
+        );
+        assert_eq!(ida.unwrap().dist, 26);
+    }
+
+    #[test]
+    fn example_b_2_has_no_way_out() {
+        let map = Maze::from_file("input/example_20_2.txt");
+        assert_eq!(map.multi_level_path(), None);
     }
 
     #[test]
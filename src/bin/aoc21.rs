@@ -15,10 +15,20 @@
 use std::convert::TryInto;
 
 use mbp_aoc2019::intcode::Computer;
+use mbp_aoc2019::springdroid::{find_program, parse_failures, Hull, Mode, Program};
 
 pub fn main() {
     println!("21a: {}", solve_a());
     println!("21b: {}", solve_b());
+    for &mode in &[Mode::Walk, Mode::Run] {
+        let (program, score) = discover(mode);
+        println!(
+            "discovered {}:\n{}score: {}",
+            mode.command(),
+            program,
+            score
+        );
+    }
 }
 
 fn solve_a() -> isize {
@@ -65,6 +75,31 @@ RUN\n",
     print_output(cpu).unwrap_or(0)
 }
 
+/// Find a program by searching for one that passes all the hulls seen so far,
+/// trying it on the real springdroid, and learning from the hull it falls
+/// into.
+fn discover(mode: Mode) -> (Program, isize) {
+    let mut hulls: Vec<Hull> = Vec::new();
+    loop {
+        let program = find_program(mode, &hulls).expect("no program passes all hulls");
+        let cpu = &mut Computer::from_file("input/input21.txt");
+        cpu.run();
+        cpu.drain_output();
+        cpu.push_input_string(&program.to_script(mode));
+        cpu.run();
+        let (text, score) = cpu.drain_output_to_string_and_score();
+        if let Some(score) = score {
+            return (program, score);
+        }
+        let new_hulls = parse_failures(&text);
+        assert!(!new_hulls.is_empty(), "no hull in output:\n{}", text);
+        for hull in new_hulls {
+            assert!(!hulls.contains(&hull), "failed on known hull {}", hull);
+            hulls.push(hull);
+        }
+    }
+}
+
 fn print_output(cpu: &mut Computer) -> Option<isize> {
     let (text, score) = cpu.drain_output_to_string_and_score();
     print!("{}", text);
@@ -94,4 +129,18 @@ mod test {
     fn solution_b() {
         assert_eq!(solve_b(), 1_136_394_042);
     }
+
+    #[test]
+    fn discover_a() {
+        let (program, score) = discover(Mode::Walk);
+        assert_eq!(score, 19_357_290);
+        assert!(program.insns.len() <= 6, "{}", program);
+    }
+
+    #[test]
+    fn discover_b() {
+        let (program, score) = discover(Mode::Run);
+        assert_eq!(score, 1_136_394_042);
+        assert!(program.insns.len() <= 10, "{}", program);
+    }
 }
@@ -101,6 +101,12 @@ fn ifbug(m: &Map, p: Point) -> usize {
     (m[p] == BUG) as usize
 }
 
+/// Work out the next state of level `m1`, given the level `m0` inside it and
+/// the level `m2` around it.
+///
+/// This doesn't use `Matrix::components` or `flood_fill`: bugs don't spread
+/// outwards from a seed, but every square looks at a count of its
+/// neighbors, and some of those are on the other levels.
 fn stacked_next_state(m0: &Map, m1: &Map, m2: &Map) -> Map {
     let mut nm = Map::new(5, 5, UNKNOWN);
     for p in m1.iter_points() {
@@ -0,0 +1,403 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The day 13 Breakout game, played by an Intcode program.
+//!
+//! The game runs until it wants joystick input, and the screen as of that
+//! point is one frame. A `Strategy` chooses the joystick position for each
+//! frame, and frames can be shown on the terminal by a `TermRenderer` or
+//! saved by a `Recorder`.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use console::Term;
+
+use crate::intcode::Computer;
+use crate::sparse_grid::SparseGrid;
+use crate::{ordering_to_int, point, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Block,
+    Paddle,
+    Ball,
+}
+
+impl Tile {
+    fn from_id(id: isize) -> Tile {
+        match id {
+            0 => Tile::Empty,
+            1 => Tile::Wall,
+            2 => Tile::Block,
+            3 => Tile::Paddle,
+            4 => Tile::Ball,
+            _ => panic!("unknown tile id {}", id),
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Wall => '#',
+            Tile::Block => '.',
+            Tile::Paddle => '_',
+            Tile::Ball => '@',
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Breakout {
+    cpu: Computer,
+    tiles: SparseGrid<Tile>,
+    score: isize,
+    ball: Option<Point>,
+    paddle: Option<Point>,
+    frames: usize,
+}
+
+impl Breakout {
+    pub fn new(cpu: Computer) -> Breakout {
+        Breakout {
+            cpu,
+            tiles: SparseGrid::new(Tile::Empty),
+            score: 0,
+            ball: None,
+            paddle: None,
+            frames: 0,
+        }
+    }
+
+    pub fn from_file(path: &str) -> Breakout {
+        Breakout::new(Computer::from_file(path))
+    }
+
+    /// Put in coins so that the game can be played, rather than just
+    /// drawing the initial screen and stopping.
+    pub fn insert_coins(&mut self) {
+        self.cpu.poke_at(0, 2);
+    }
+
+    /// Run the game until it wants input or stops, and update the screen.
+    ///
+    /// Returns false once the game is over.
+    pub fn advance(&mut self) -> bool {
+        self.cpu.run();
+        for chunk in self.cpu.drain_output().chunks(3) {
+            if let [x, y, t] = *chunk {
+                if x == -1 && y == 0 {
+                    self.score = t;
+                } else {
+                    let p = point(x, y);
+                    let tile = Tile::from_id(t);
+                    match tile {
+                        Tile::Ball => self.ball = Some(p),
+                        Tile::Paddle => self.paddle = Some(p),
+                        _ => (),
+                    }
+                    self.tiles.set(p, tile);
+                }
+            } else {
+                panic!("bad chunk? {:?}", chunk);
+            }
+        }
+        self.frames += 1;
+        !self.cpu.is_halted()
+    }
+
+    /// Move the joystick left (-1), right (1) or leave it neutral (0).
+    pub fn push_joystick(&mut self, j: isize) {
+        assert!((-1..=1).contains(&j), "bad joystick position {}", j);
+        self.cpu.push_input(j)
+    }
+
+    /// Play until the game stops, asking the strategy how to move the
+    /// joystick, and calling `on_frame` after every frame.
+    ///
+    /// Returns the final score.
+    pub fn play<S, F>(&mut self, strategy: &mut S, mut on_frame: F) -> isize
+    where
+        S: Strategy + ?Sized,
+        F: FnMut(&Breakout),
+    {
+        loop {
+            let running = self.advance();
+            on_frame(self);
+            if !running {
+                return self.score;
+            }
+            let j = strategy.joystick(self);
+            self.push_joystick(j);
+        }
+    }
+
+    pub fn tiles(&self) -> &SparseGrid<Tile> {
+        &self.tiles
+    }
+
+    pub fn tile(&self, p: Point) -> Tile {
+        *self.tiles.get(p)
+    }
+
+    pub fn score(&self) -> isize {
+        self.score
+    }
+
+    pub fn ball(&self) -> Option<Point> {
+        self.ball
+    }
+
+    pub fn paddle(&self) -> Option<Point> {
+        self.paddle
+    }
+
+    /// Number of frames shown so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn count_blocks(&self) -> usize {
+        self.tiles.iter().filter(|(_, t)| **t == Tile::Block).count()
+    }
+
+    /// The top-left and bottom-right corners of everything drawn so far,
+    /// or None if nothing has been drawn yet.
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        self.tiles.bounds()
+    }
+
+    /// Draw the screen as text, with the score on the first line.
+    pub fn draw(&self) -> String {
+        let mut s = format!("Score: {}\n", self.score);
+        s.push_str(&self.tiles.render(|t| t.to_char()));
+        s
+    }
+}
+
+/// Decides how to move the joystick for each frame.
+pub trait Strategy {
+    /// Return -1 to move left, 1 to move right, or 0 to stay still.
+    fn joystick(&mut self, game: &Breakout) -> isize;
+}
+
+/// Keep the paddle under the ball.
+///
+/// This turns out to be enough to win, because the paddle can move as fast
+/// as the ball.
+#[derive(Debug, Default)]
+pub struct FollowBall;
+
+impl Strategy for FollowBall {
+    fn joystick(&mut self, game: &Breakout) -> isize {
+        ordering_to_int(game.ball.unwrap().x.cmp(&game.paddle.unwrap().x))
+    }
+}
+
+/// Move the paddle to where the ball will come down, working out its
+/// heading from the last frame and bouncing it off the side walls.
+///
+/// Blocks can deflect the ball unpredictably, so this only predicts once
+/// the ball is falling with no blocks left below it, and otherwise follows
+/// the ball.
+#[derive(Debug, Default)]
+pub struct Predictive {
+    last_ball: Option<Point>,
+}
+
+impl Predictive {
+    /// The column the ball will reach when it comes down to just above the
+    /// paddle, if it's falling clear of any blocks.
+    pub fn landing_x(&self, game: &Breakout) -> Option<isize> {
+        let ball = game.ball?;
+        let last = self.last_ball?;
+        let paddle = game.paddle?;
+        let (dx, dy) = (ball.x - last.x, ball.y - last.y);
+        if dy <= 0
+            || game
+                .tiles
+                .iter()
+                .any(|(p, t)| *t == Tile::Block && p.y > ball.y)
+        {
+            return None;
+        }
+        // Walls are at the far left and right, so the ball stays between.
+        let (min, max) = game.bounds()?;
+        let (left, right) = (min.x + 1, max.x - 1);
+        let x = ball.x + dx * (paddle.y - 1 - ball.y);
+        Some(reflect(x, left, right))
+    }
+}
+
+impl Strategy for Predictive {
+    fn joystick(&mut self, game: &Breakout) -> isize {
+        let ball = game.ball.unwrap();
+        let target = self.landing_x(game).unwrap_or(ball.x);
+        self.last_ball = Some(ball);
+        ordering_to_int(target.cmp(&game.paddle.unwrap().x))
+    }
+}
+
+/// Fold `x` back into `left..=right` as if bouncing off both ends.
+fn reflect(x: isize, left: isize, right: isize) -> isize {
+    let width = right - left;
+    if width == 0 {
+        return left;
+    }
+    let m = (x - left).rem_euclid(2 * width);
+    if m <= width {
+        left + m
+    } else {
+        right - (m - width)
+    }
+}
+
+/// Shows frames on the terminal.
+pub struct TermRenderer {
+    term: Term,
+    /// Pause after drawing each frame.
+    pub delay: Duration,
+    /// Draw only one in this many frames, to run faster.
+    pub every: usize,
+}
+
+impl TermRenderer {
+    pub fn new() -> TermRenderer {
+        TermRenderer {
+            term: Term::stdout(),
+            delay: Duration::from_millis(0),
+            every: 1,
+        }
+    }
+
+    /// Draw frames at up to this many per second.
+    pub fn with_fps(mut self, fps: u32) -> TermRenderer {
+        assert!(fps > 0);
+        self.delay = Duration::from_secs(1) / fps;
+        self
+    }
+
+    pub fn with_every(mut self, every: usize) -> TermRenderer {
+        assert!(every > 0);
+        self.every = every;
+        self
+    }
+
+    pub fn show(&mut self, game: &Breakout) -> io::Result<()> {
+        if game.frames() == 1 {
+            self.term.clear_screen()?;
+        }
+        if !game.frames().is_multiple_of(self.every) && !game.cpu.is_halted() {
+            return Ok(());
+        }
+        self.term.move_cursor_to(0, 0)?;
+        self.term.write_all(game.draw().as_bytes())?;
+        if self.delay > Duration::from_millis(0) {
+            std::thread::sleep(self.delay);
+        }
+        Ok(())
+    }
+}
+
+impl Default for TermRenderer {
+    fn default() -> TermRenderer {
+        TermRenderer::new()
+    }
+}
+
+/// Writes every frame to a file, for replaying later.
+///
+/// Each frame is a header line `Frame N`, then the screen as drawn by
+/// `Breakout::draw`, then a blank line.
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Recorder> {
+        Ok(Recorder {
+            out: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub fn record(&mut self, game: &Breakout) -> io::Result<()> {
+        writeln!(self.out, "Frame {}", game.frames())?;
+        writeln!(self.out, "{}", game.draw())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reflect_off_walls() {
+        assert_eq!(reflect(5, 1, 10), 5);
+        assert_eq!(reflect(12, 1, 10), 8);
+        assert_eq!(reflect(0, 1, 10), 2);
+        assert_eq!(reflect(-8, 1, 10), 10);
+        assert_eq!(reflect(20, 1, 10), 2);
+    }
+
+    #[test]
+    fn first_frame() {
+        let mut game = Breakout::from_file("input/input13.txt");
+        assert_eq!(game.bounds(), None);
+        assert!(!game.advance());
+        assert_eq!(game.bounds(), Some((point(0, 0), point(37, 20))));
+        assert_eq!(game.count_blocks(), 228);
+        assert!(game.ball().is_some());
+        assert!(game.paddle().is_some());
+        let drawn = game.draw();
+        assert!(drawn.starts_with("Score: 0\n####"));
+    }
+
+    #[test]
+    fn strategies_win() {
+        for strategy in &mut [
+            Box::new(FollowBall) as Box<dyn Strategy>,
+            Box::new(Predictive::default()),
+        ] {
+            let mut game = Breakout::from_file("input/input13.txt");
+            game.insert_coins();
+            let score = game.play(strategy.as_mut(), |_| ());
+            assert_eq!(score, 10776);
+            assert_eq!(game.count_blocks(), 0);
+        }
+    }
+
+    #[test]
+    fn record_frames() {
+        let path = std::env::temp_dir().join(format!(
+            "aoc2019-breakout-record-frames-{}.txt",
+            std::process::id()
+        ));
+        let mut recorder = Recorder::create(&path).unwrap();
+        let mut game = Breakout::from_file("input/input13.txt");
+        game.advance();
+        recorder.record(&game).unwrap();
+        recorder.finish().unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("Frame 1\nScore: 0\n"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Try many candidates on several threads at once.
+//!
+//! Several puzzles are solved by running an independent Intcode program
+//! for each of many inputs. These helpers hand candidates from an iterator
+//! to worker threads one at a time, so the iterator itself is only
+//! advanced under a lock and can be lazy.
+//!
+//! Results are the same as a sequential search, whatever order the
+//! threads happen to finish in: ties go to the earliest candidate.
+
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// The number of worker threads to use: one per available CPU.
+pub fn worker_count() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Call `f` on every candidate, with its position in the iterator, until
+/// it returns true.
+///
+/// Once `f` returns true for some candidate, no later candidates are
+/// started, but every earlier one is still tried. Returns the position of
+/// the first candidate for which `f` returned true.
+fn for_each_until<I, T, F>(candidates: I, f: F) -> Option<usize>
+where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: Send,
+    T: Send,
+    F: Fn(usize, T) -> bool + Sync,
+{
+    let next = Mutex::new(candidates.into_iter().enumerate());
+    let stop = AtomicUsize::new(usize::MAX);
+    thread::scope(|s| {
+        for _ in 0..worker_count() {
+            s.spawn(|| loop {
+                let (i, c) = match next.lock().unwrap().next() {
+                    Some(ic) => ic,
+                    None => return,
+                };
+                if i > stop.load(Ordering::SeqCst) {
+                    return;
+                }
+                if f(i, c) {
+                    stop.fetch_min(i, Ordering::SeqCst);
+                }
+            });
+        }
+    });
+    match stop.into_inner() {
+        usize::MAX => None,
+        i => Some(i),
+    }
+}
+
+/// The candidate with the largest key, and that key, or None if there are
+/// no candidates.
+pub fn par_max_by_key<I, T, K, F>(candidates: I, f: F) -> Option<(K, T)>
+where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: Send,
+    T: Send,
+    K: Ord + Send,
+    F: Fn(&T) -> K + Sync,
+{
+    max_by_key_until(candidates, f, |_| false)
+}
+
+/// Like `par_max_by_key`, but when it's known that no key can be more than
+/// `bound`, stop as soon as some candidate reaches it.
+pub fn par_max_by_key_bounded<I, T, K, F>(candidates: I, f: F, bound: K) -> Option<(K, T)>
+where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: Send,
+    T: Send,
+    K: Ord + Send + Sync,
+    F: Fn(&T) -> K + Sync,
+{
+    max_by_key_until(candidates, f, |k| *k >= bound)
+}
+
+fn max_by_key_until<I, T, K, F, D>(candidates: I, f: F, done: D) -> Option<(K, T)>
+where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: Send,
+    T: Send,
+    K: Ord + Send,
+    F: Fn(&T) -> K + Sync,
+    D: Fn(&K) -> bool + Sync,
+{
+    let best: Mutex<Option<(K, Reverse<usize>, T)>> = Mutex::new(None);
+    for_each_until(candidates, |i, c| {
+        let k = f(&c);
+        let is_done = done(&k);
+        let mut best = best.lock().unwrap();
+        if best
+            .as_ref()
+            .is_none_or(|(bk, bi, _)| (&k, Reverse(i)) > (bk, *bi))
+        {
+            *best = Some((k, Reverse(i), c));
+        }
+        is_done
+    });
+    best.into_inner().unwrap().map(|(k, _, c)| (k, c))
+}
+
+/// The result of `f` on the first candidate for which it returns Some,
+/// like `Iterator::find_map`.
+///
+/// Candidates after the first match may never be tried.
+pub fn par_find_map<I, T, R, F>(candidates: I, f: F) -> Option<R>
+where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: Send,
+    T: Send,
+    R: Send,
+    F: Fn(T) -> Option<R> + Sync,
+{
+    let found: Mutex<Option<(usize, R)>> = Mutex::new(None);
+    for_each_until(candidates, |i, c| match f(c) {
+        Some(r) => {
+            let mut found = found.lock().unwrap();
+            if found.as_ref().is_none_or(|(fi, _)| i < *fi) {
+                *found = Some((i, r));
+            }
+            true
+        }
+        None => false,
+    });
+    found.into_inner().unwrap().map(|(_, r)| r)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn max_by_key() {
+        assert_eq!(
+            par_max_by_key(0..1000, |&i: &i32| (i * 37) % 101),
+            Some((100, 30))
+        );
+        // The earliest of equal keys wins.
+        assert_eq!(par_max_by_key(0..1000, |&i| i % 10), Some((9, 9)));
+        assert_eq!(par_max_by_key(Vec::<u8>::new(), |&i| i), None);
+    }
+
+    #[test]
+    fn bounded_stops_early() {
+        let tried = AtomicUsize::new(0);
+        let best = par_max_by_key_bounded(
+            0..1_000_000,
+            |&i| {
+                tried.fetch_add(1, Ordering::SeqCst);
+                i % 100
+            },
+            99,
+        );
+        assert_eq!(best, Some((99, 99)));
+        assert!(tried.into_inner() < 1_000_000);
+    }
+
+    #[test]
+    fn find_map() {
+        let tried = AtomicUsize::new(0);
+        let found = par_find_map(1..1_000_000, |i: u64| {
+            tried.fetch_add(1, Ordering::SeqCst);
+            if i * i > 5000 && i.is_multiple_of(7) {
+                Some(i * 10)
+            } else {
+                None
+            }
+        });
+        assert_eq!(found, Some(770));
+        assert!(tried.into_inner() < 1_000_000);
+        assert_eq!(par_find_map(0..100, |_: u8| None::<u8>), None);
+    }
+}
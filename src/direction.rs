@@ -0,0 +1,224 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compass directions, and a turtle that moves and turns.
+//!
+//! North is up the screen, which is decreasing y.
+
+use std::ops::{Add, AddAssign};
+
+use crate::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    North,
+    South,
+    West,
+    East,
+}
+
+use Direction::*;
+
+impl Direction {
+    /// All directions, in the order of their movement command codes.
+    pub const ALL: [Direction; 4] = [North, South, West, East];
+
+    pub fn turn_left(self) -> Direction {
+        match self {
+            North => West,
+            West => South,
+            South => East,
+            East => North,
+        }
+    }
+
+    pub fn turn_right(self) -> Direction {
+        self.turn_left().reverse()
+    }
+
+    pub fn reverse(self) -> Direction {
+        match self {
+            North => South,
+            South => North,
+            West => East,
+            East => West,
+        }
+    }
+
+    /// The point one step from `p` in this direction.
+    pub fn apply(self, p: Point) -> Point {
+        match self {
+            North => p.up(),
+            South => p.down(),
+            West => p.left(),
+            East => p.right(),
+        }
+    }
+
+    /// Parse one of `^v<>`.
+    pub fn from_arrow(c: char) -> Option<Direction> {
+        match c {
+            '^' => Some(North),
+            'v' => Some(South),
+            '<' => Some(West),
+            '>' => Some(East),
+            _ => None,
+        }
+    }
+
+    pub fn to_arrow(self) -> char {
+        match self {
+            North => '^',
+            South => 'v',
+            West => '<',
+            East => '>',
+        }
+    }
+
+    /// Parse the movement commands used by Intcode robots: 1 for north, 2
+    /// for south, 3 for west and 4 for east.
+    pub fn from_command(c: isize) -> Option<Direction> {
+        match c {
+            1..=4 => Some(Direction::ALL[(c - 1) as usize]),
+            _ => None,
+        }
+    }
+
+    /// The movement command for this direction; the inverse of
+    /// `from_command`.
+    pub fn command(self) -> isize {
+        match self {
+            North => 1,
+            South => 2,
+            West => 3,
+            East => 4,
+        }
+    }
+}
+
+impl Add<Direction> for Point {
+    type Output = Point;
+
+    fn add(self, d: Direction) -> Point {
+        d.apply(self)
+    }
+}
+
+impl AddAssign<Direction> for Point {
+    fn add_assign(&mut self, d: Direction) {
+        *self = d.apply(*self)
+    }
+}
+
+/// Something with a position and a heading, that can move forward and
+/// turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Turtle {
+    pub pos: Point,
+    pub heading: Direction,
+}
+
+impl Turtle {
+    pub fn new(pos: Point, heading: Direction) -> Turtle {
+        Turtle { pos, heading }
+    }
+
+    /// The point just in front.
+    pub fn ahead(&self) -> Point {
+        self.pos + self.heading
+    }
+
+    /// The point just to the left.
+    pub fn left(&self) -> Point {
+        self.pos + self.heading.turn_left()
+    }
+
+    /// The point just to the right.
+    pub fn right(&self) -> Point {
+        self.pos + self.heading.turn_right()
+    }
+
+    pub fn forward(&mut self) {
+        self.pos += self.heading
+    }
+
+    pub fn turn_left(&mut self) {
+        self.heading = self.heading.turn_left()
+    }
+
+    pub fn turn_right(&mut self) {
+        self.heading = self.heading.turn_right()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::point;
+
+    #[test]
+    fn turns() {
+        assert_eq!(North.turn_left(), West);
+        assert_eq!(North.turn_right(), East);
+        assert_eq!(West.turn_right().turn_right(), East);
+        for &d in &Direction::ALL {
+            assert_eq!(d.turn_left().turn_left(), d.reverse());
+            assert_eq!(d.turn_left().turn_right(), d);
+            assert_eq!(d.reverse().reverse(), d);
+        }
+    }
+
+    #[test]
+    fn conversions() {
+        for &d in &Direction::ALL {
+            assert_eq!(Direction::from_arrow(d.to_arrow()), Some(d));
+            assert_eq!(Direction::from_command(d.command()), Some(d));
+        }
+        assert_eq!(Direction::from_arrow('x'), None);
+        assert_eq!(Direction::from_command(0), None);
+        assert_eq!(Direction::from_command(5), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn step_out_of_range() {
+        West.apply(point(isize::MIN, 0));
+    }
+
+    #[test]
+    fn stepping() {
+        assert_eq!(point(3, 3) + North, point(3, 2));
+        assert_eq!(point(3, 3) + West, point(2, 3));
+        let mut p = point(0, 0);
+        p += South;
+        p += East;
+        assert_eq!(p, point(1, 1));
+    }
+
+    #[test]
+    fn turtle() {
+        let mut t = Turtle::new(point(0, 0), North);
+        assert_eq!(t.ahead(), point(0, -1));
+        assert_eq!(t.left(), point(-1, 0));
+        assert_eq!(t.right(), point(1, 0));
+        t.forward();
+        t.turn_right();
+        t.forward();
+        t.forward();
+        assert_eq!(t, Turtle::new(point(2, -1), East));
+        t.turn_left();
+        t.turn_left();
+        assert_eq!(t.heading, West);
+    }
+}
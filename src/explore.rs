@@ -0,0 +1,284 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Explore an unknown grid by moving around in it, through an oracle that
+//! reports what's in each square it tries to enter.
+//!
+//! The explorer starts at `point(0, 0)` and can only find out about squares
+//! next to where it is, so the map is built up as it goes.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::direction::Direction;
+use crate::shortest_path::bfs_distances;
+use crate::sparse_grid::SparseGrid;
+use crate::{point, Matrix, Point};
+
+/// Something that can try to move one step, and say what it found there.
+pub trait Oracle {
+    type Cell: Clone;
+
+    /// Try to step in a direction, and return what's in that square.
+    ///
+    /// If the square is open the oracle moves there; otherwise it stays
+    /// where it was.
+    fn try_move(&mut self, dir: Direction) -> Self::Cell;
+
+    /// True if the oracle can move into a square containing `cell`.
+    fn is_open(&self, cell: &Self::Cell) -> bool;
+}
+
+/// Everything discovered so far.
+#[derive(Debug, Clone)]
+pub struct Explored<C> {
+    /// Contents of every square that has been seen, and None for squares
+    /// that haven't.
+    pub cells: SparseGrid<Option<C>>,
+    /// Squares that can be entered.
+    pub open: BTreeSet<Point>,
+}
+
+impl<C: Clone> Explored<C> {
+    fn new(start: C) -> Explored<C> {
+        let origin = point(0, 0);
+        let mut cells = SparseGrid::new(None);
+        cells.set(origin, Some(start));
+        let mut open = BTreeSet::new();
+        open.insert(origin);
+        Explored { cells, open }
+    }
+
+    fn insert(&mut self, p: Point, cell: C, open: bool) {
+        self.cells.set(p, Some(cell));
+        if open {
+            self.open.insert(p);
+        }
+    }
+
+    pub fn get(&self, p: Point) -> Option<&C> {
+        self.cells.get(p).as_ref()
+    }
+
+    /// Find the first point, in row-major order, whose contents match.
+    pub fn find<F: Fn(&C) -> bool>(&self, f: F) -> Option<Point> {
+        self.cells
+            .iter()
+            .find(|(_, c)| c.as_ref().is_some_and(&f))
+            .map(|(p, _)| *p)
+    }
+
+    /// The top-left and bottom-right corners of everything seen.
+    pub fn bounds(&self) -> (Point, Point) {
+        // The start is always seen, so there's at least one square.
+        self.cells.bounds().unwrap()
+    }
+
+    /// Shortest number of steps to every open square reachable from `from`,
+    /// through squares seen so far.
+    pub fn distances_from(&self, from: Point) -> BTreeMap<Point, usize> {
+        bfs_distances(Some(from), &mut |p: Point| {
+            p.neighbors()
+                .into_iter()
+                .filter(|np| self.open.contains(np))
+                .collect()
+        })
+        .into_iter()
+        .collect()
+    }
+
+    /// Copy into a matrix, filling unseen squares with `unknown`.
+    ///
+    /// Returns the matrix and the position of the origin within it.
+    pub fn to_matrix(&self, unknown: C) -> (Matrix<C>, Point) {
+        let (cells, min) = self.cells.to_matrix();
+        let mut m = Matrix::new(cells.width(), cells.height(), unknown);
+        for p in cells.iter_points() {
+            if let Some(c) = &cells[p] {
+                m[p] = c.clone();
+            }
+        }
+        (m, -min)
+    }
+
+    /// Draw what's been seen, with `@` at `pos` and spaces for unseen
+    /// squares.
+    pub fn draw<F: Fn(&C) -> char>(&self, pos: Point, to_char: F) -> String {
+        let (min, max) = self.bounds();
+        let mut s = String::new();
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let p = point(x, y);
+                if p == pos {
+                    s.push('@')
+                } else if let Some(c) = self.get(p) {
+                    s.push(to_char(c))
+                } else {
+                    s.push(' ')
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
+}
+
+/// Explore by walking depth-first, backing up when there's nowhere new to
+/// go. This needs only one oracle, but doesn't find shortest paths along
+/// the way: use `Explored::distances_from` afterwards.
+///
+/// `start` is the contents of the starting square. `on_progress` is called
+/// with the map so far and the oracle's position after every move.
+pub fn explore_dfs<O, F>(oracle: &mut O, start: O::Cell, mut on_progress: F) -> Explored<O::Cell>
+where
+    O: Oracle,
+    F: FnMut(&Explored<O::Cell>, Point),
+{
+    let mut explored = Explored::new(start);
+    // Steps taken to reach the current position, to retrace when backing up.
+    let mut trail: Vec<Direction> = Vec::new();
+    let mut pos = point(0, 0);
+    loop {
+        let next = Direction::ALL
+            .iter()
+            .copied()
+            .find(|s| !explored.cells.contains(s.apply(pos)));
+        if let Some(dir) = next {
+            let np = dir.apply(pos);
+            let cell = oracle.try_move(dir);
+            let open = oracle.is_open(&cell);
+            explored.insert(np, cell, open);
+            if open {
+                pos = np;
+                trail.push(dir);
+                on_progress(&explored, pos);
+            }
+        } else if let Some(dir) = trail.pop() {
+            let back = dir.reverse();
+            let cell = oracle.try_move(back);
+            assert!(oracle.is_open(&cell), "can't retrace step {:?}", back);
+            pos = back.apply(pos);
+            on_progress(&explored, pos);
+        } else {
+            return explored;
+        }
+    }
+}
+
+/// Explore breadth-first, by cloning the oracle at every square so that
+/// exploration can continue from there later without walking back.
+///
+/// Squares are visited in order of distance from the start.
+///
+/// `start` is the contents of the starting square. `on_progress` is called
+/// with the map so far and the position of each oracle as it moves.
+pub fn explore_bfs<O, F>(oracle: O, start: O::Cell, mut on_progress: F) -> Explored<O::Cell>
+where
+    O: Oracle + Clone,
+    F: FnMut(&Explored<O::Cell>, Point),
+{
+    let mut explored = Explored::new(start);
+    let mut queue: VecDeque<(Point, O)> = VecDeque::new();
+    queue.push_back((point(0, 0), oracle));
+    while let Some((pos, oracle)) = queue.pop_front() {
+        for &dir in &Direction::ALL {
+            let np = dir.apply(pos);
+            if explored.cells.contains(np) {
+                continue;
+            }
+            let mut new_oracle = oracle.clone();
+            let cell = new_oracle.try_move(dir);
+            let open = new_oracle.is_open(&cell);
+            explored.insert(np, cell, open);
+            if open {
+                on_progress(&explored, np);
+                queue.push_back((np, new_oracle));
+            }
+        }
+    }
+    explored
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// An oracle that walks around a known maze.
+    #[derive(Clone)]
+    struct MazeWalker {
+        maze: Matrix<char>,
+        pos: Point,
+    }
+
+    impl Oracle for MazeWalker {
+        type Cell = char;
+
+        fn try_move(&mut self, dir: Direction) -> char {
+            let np = self.pos + dir;
+            let c = self.maze.try_get(np).unwrap_or('#');
+            if c != '#' {
+                self.pos = np;
+            }
+            c
+        }
+
+        fn is_open(&self, cell: &char) -> bool {
+            *cell != '#'
+        }
+    }
+
+    const MAZE: &str = "\
+#######
+#..#..#
+#.##.##
+#...*.#
+#######
+";
+
+    fn walker() -> MazeWalker {
+        MazeWalker {
+            maze: Matrix::from_string_lines(MAZE),
+            pos: point(1, 1),
+        }
+    }
+
+    #[test]
+    fn dfs_and_bfs_agree() {
+        let mut moves = 0;
+        let dfs = explore_dfs(&mut walker(), '.', |_, _| moves += 1);
+        let bfs = explore_bfs(walker(), '.', |_, _| ());
+        assert_eq!(dfs.cells, bfs.cells);
+        assert_eq!(dfs.open, bfs.open);
+        // Every open square is entered and then left again, except the start.
+        assert_eq!(moves, 2 * (dfs.open.len() - 1));
+
+        let star = dfs.find(|c| *c == '*').unwrap();
+        assert_eq!(star, point(3, 2));
+        assert_eq!(dfs.distances_from(point(0, 0))[&star], 5);
+
+        // The corners are never next to an open square, so never seen.
+        let (m, origin) = dfs.to_matrix(' ');
+        assert_eq!(origin, point(1, 1));
+        assert_eq!((m.width(), m.height()), (7, 5));
+        assert_eq!(m[point(4, 3)], '*');
+        assert_eq!(m[point(0, 0)], ' ');
+    }
+
+    #[test]
+    fn draw_progress() {
+        let explored = explore_bfs(walker(), '.', |_, _| ());
+        assert!(explored
+            .draw(point(0, 0), |c| *c)
+            .starts_with(" ## ## \n#@.#..#\n"));
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A dense 2d matrix that grows in any direction when written.
+//!
+//! Coordinates can be negative. The top-left corner of everything written
+//! so far is the `origin`, which moves up and left as the matrix grows.
+//! Reading a point that has never been written gives the default value.
+//!
+//! Storage grows by at least the current size whenever it has to grow, so
+//! a robot wandering outwards one step at a time doesn't copy the whole
+//! matrix on every step.
+
+use std::ops::{Index, IndexMut};
+
+use crate::{point, Matrix, Point};
+
+/// The smallest number of rows or columns to add when growing.
+const MIN_GROWTH: isize = 4;
+
+#[derive(Clone)]
+pub struct GrowingMatrix<T> {
+    /// Storage, which may be larger than what's been written.
+    store: Matrix<T>,
+    /// Position of the top-left of `store`.
+    store_origin: Point,
+    /// Top-left and bottom-right corners of everything written.
+    bounds: Option<(Point, Point)>,
+    default: T,
+}
+
+impl<T: Clone> GrowingMatrix<T> {
+    /// Make an empty matrix where every point reads as `default`.
+    pub fn new(default: T) -> GrowingMatrix<T> {
+        GrowingMatrix {
+            store: Matrix::new(0, 0, default.clone()),
+            store_origin: point(0, 0),
+            bounds: None,
+            default,
+        }
+    }
+
+    /// Copy a matrix, putting its top-left corner at `origin`.
+    pub fn from_matrix(m: Matrix<T>, origin: Point, default: T) -> GrowingMatrix<T> {
+        let bounds = if m.width() > 0 && m.height() > 0 {
+            Some((
+                origin,
+                point(
+                    origin.x + m.width() as isize - 1,
+                    origin.y + m.height() as isize - 1,
+                ),
+            ))
+        } else {
+            None
+        };
+        GrowingMatrix {
+            store: m,
+            store_origin: origin,
+            bounds,
+            default,
+        }
+    }
+
+    /// The top-left corner of everything written so far.
+    pub fn origin(&self) -> Point {
+        self.bounds.map_or(point(0, 0), |b| b.0)
+    }
+
+    /// The top-left and bottom-right corners of everything written, or
+    /// None if nothing has been written.
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        self.bounds
+    }
+
+    pub fn width(&self) -> usize {
+        self.bounds
+            .map_or(0, |(min, max)| (max.x - min.x + 1) as usize)
+    }
+
+    pub fn height(&self) -> usize {
+        self.bounds
+            .map_or(0, |(min, max)| (max.y - min.y + 1) as usize)
+    }
+
+    fn in_bounds(&self, p: Point) -> bool {
+        match self.bounds {
+            Some((min, max)) => p.x >= min.x && p.y >= min.y && p.x <= max.x && p.y <= max.y,
+            None => false,
+        }
+    }
+
+    fn store_point(&self, p: Point) -> Point {
+        point(p.x - self.store_origin.x, p.y - self.store_origin.y)
+    }
+
+    /// The value at `p`, or None if it's outside everything written.
+    pub fn try_get(&self, p: Point) -> Option<T> {
+        if self.in_bounds(p) {
+            Some(self[p].clone())
+        } else {
+            None
+        }
+    }
+
+    /// Return a vec of the 4 neighboring points (if within the bounds) and
+    /// their values.
+    pub fn neighbors4(&self, p: Point) -> Vec<(Point, &T)> {
+        p.neighbors()
+            .into_iter()
+            .filter(|np| self.in_bounds(*np))
+            .map(|np| (np, &self[np]))
+            .collect()
+    }
+
+    /// Iterate every point within the bounds, in row-major order.
+    pub fn iter_points<'a>(&'a self) -> Box<dyn Iterator<Item = Point> + 'a> {
+        match self.bounds {
+            Some((min, max)) => Box::new(
+                (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| point(x, y))),
+            ),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    /// Copy the written area into a plain matrix.
+    ///
+    /// Returns the matrix and the position of its top-left corner.
+    pub fn to_matrix(&self) -> (Matrix<T>, Point) {
+        let origin = self.origin();
+        let mut m = Matrix::new(self.width(), self.height(), self.default.clone());
+        for p in self.iter_points() {
+            m[point(p.x - origin.x, p.y - origin.y)] = self[p].clone();
+        }
+        (m, origin)
+    }
+
+    /// Make sure `p` is in storage, reallocating if necessary, and extend
+    /// the bounds to include it.
+    fn grow_to(&mut self, p: Point) {
+        self.bounds = Some(match self.bounds {
+            None => (p, p),
+            Some((min, max)) => (
+                point(min.x.min(p.x), min.y.min(p.y)),
+                point(max.x.max(p.x), max.y.max(p.y)),
+            ),
+        });
+        let sp = self.store_point(p);
+        let (w, h) = (self.store.width() as isize, self.store.height() as isize);
+        if sp.x >= 0 && sp.y >= 0 && sp.x < w && sp.y < h {
+            return;
+        }
+        let grow_x = w.max(MIN_GROWTH);
+        let grow_y = h.max(MIN_GROWTH);
+        let left = if sp.x < 0 { grow_x.max(-sp.x) } else { 0 };
+        let top = if sp.y < 0 { grow_y.max(-sp.y) } else { 0 };
+        let right = if sp.x >= w {
+            grow_x.max(sp.x - w + 1)
+        } else {
+            0
+        };
+        let bottom = if sp.y >= h {
+            grow_y.max(sp.y - h + 1)
+        } else {
+            0
+        };
+        let mut store = Matrix::new(
+            (w + left + right) as usize,
+            (h + top + bottom) as usize,
+            self.default.clone(),
+        );
+        for old in self.store.iter_points() {
+            store[point(old.x + left, old.y + top)] = self.store[old].clone();
+        }
+        self.store = store;
+        self.store_origin = point(self.store_origin.x - left, self.store_origin.y - top);
+    }
+}
+
+impl GrowingMatrix<char> {
+    pub fn to_string_lines(&self) -> String {
+        self.to_matrix().0.to_string_lines()
+    }
+}
+
+impl<T: Clone> Index<Point> for GrowingMatrix<T> {
+    type Output = T;
+
+    /// The value at `p`, or the default if `p` hasn't been allocated.
+    fn index(&self, p: Point) -> &T {
+        let sp = self.store_point(p);
+        if sp.x >= 0
+            && sp.y >= 0
+            && sp.x < self.store.width() as isize
+            && sp.y < self.store.height() as isize
+        {
+            &self.store[sp]
+        } else {
+            &self.default
+        }
+    }
+}
+
+impl<T: Clone> IndexMut<Point> for GrowingMatrix<T> {
+    /// Get a mutable reference to `p`, growing the matrix to include it.
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        self.grow_to(p);
+        let sp = self.store_point(p);
+        &mut self.store[sp]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn grow_every_way() {
+        let mut m = GrowingMatrix::new('.');
+        assert_eq!(m.width(), 0);
+        assert_eq!(m.to_string_lines(), "");
+        m[point(0, 0)] = 'o';
+        m[point(-3, -1)] = 'a';
+        m[point(2, 1)] = 'b';
+        assert_eq!(m.origin(), point(-3, -1));
+        assert_eq!((m.width(), m.height()), (6, 3));
+        assert_eq!(m.to_string_lines(), "a.....\n...o..\n.....b\n");
+        assert_eq!(m[point(100, -100)], '.');
+        assert_eq!(m.try_get(point(-3, -1)), Some('a'));
+        assert_eq!(m.try_get(point(3, 0)), None);
+        assert_eq!(m.iter_points().count(), 18);
+        assert_eq!(m.iter_points().next(), Some(point(-3, -1)));
+
+        let mut n = m.neighbors4(point(-3, -1));
+        n.sort();
+        assert_eq!(n, vec![(point(-2, -1), &'.'), (point(-3, 0), &'.')]);
+    }
+
+    #[test]
+    fn walk_far() {
+        let mut m = GrowingMatrix::new(0);
+        for i in 0..1000 {
+            m[point(-i, i / 2)] = i;
+        }
+        assert_eq!(m.origin(), point(-999, 0));
+        assert_eq!((m.width(), m.height()), (1000, 500));
+        assert_eq!(m[point(-999, 499)], 999);
+        assert!(m.store.width() < 2100);
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        let plain = Matrix::from_string_lines("ab\ncd\n");
+        let mut m = GrowingMatrix::from_matrix(plain.clone(), point(-1, -1), ' ');
+        assert_eq!(m[point(0, 0)], 'd');
+        let (back, origin) = m.to_matrix();
+        assert!(back == plain);
+        assert_eq!(origin, point(-1, -1));
+        m[point(1, -1)] = 'e';
+        assert_eq!(m.to_string_lines(), "abe\ncd \n");
+    }
+}
@@ -0,0 +1,251 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The day 11 hull-painting robot.
+//!
+//! The robot starts at `point(0, 0)` facing up. At every step its program
+//! reads the color of the panel underneath, paints it, and turns left or
+//! right before moving forward one panel.
+//!
+//! The painted hull can be drawn as ASCII art, or exported as plain
+//! (ASCII) PBM or PGM images, which most image viewers can open.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::direction::{Direction, Turtle};
+use crate::growing_matrix::GrowingMatrix;
+use crate::intcode::Computer;
+use crate::{point, Point};
+
+/// Gray level of the robot in animation frames.
+const ROBOT_GRAY: u8 = 128;
+
+/// One panel painted by the robot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Paint {
+    pub pos: Point,
+    /// True for white, false for black.
+    pub white: bool,
+}
+
+pub struct HullPainter {
+    cpu: Computer,
+    turtle: Turtle,
+    panels: GrowingMatrix<bool>,
+    history: Vec<Paint>,
+    start_white: bool,
+}
+
+impl HullPainter {
+    /// Make a robot to run the given program, starting on either a white or
+    /// black panel.
+    pub fn new(cpu: Computer, start_white: bool) -> HullPainter {
+        let mut panels = GrowingMatrix::new(false);
+        if start_white {
+            panels[point(0, 0)] = true;
+        }
+        HullPainter {
+            cpu,
+            turtle: Turtle::new(point(0, 0), Direction::North),
+            panels,
+            history: Vec::new(),
+            start_white,
+        }
+    }
+
+    pub fn from_file(path: &str, start_white: bool) -> HullPainter {
+        HullPainter::new(Computer::from_file(path), start_white)
+    }
+
+    /// Run the robot until its program stops.
+    pub fn run(&mut self) {
+        loop {
+            self.cpu.clear_input(); // in case it wasn't read
+            let pos = self.turtle.pos;
+            self.cpu.push_input(self.is_white(pos).into());
+            let white = match self.cpu.run_until_output() {
+                Some(0) => false,
+                Some(1) => true,
+                Some(other) => panic!("bad color {}", other),
+                None => return,
+            };
+            self.panels[pos] = white;
+            self.history.push(Paint { pos, white });
+            match self.cpu.run_until_output() {
+                Some(0) => self.turtle.turn_left(),
+                Some(1) => self.turtle.turn_right(),
+                Some(other) => panic!("bad turn {}", other),
+                None => return,
+            };
+            self.turtle.forward();
+        }
+    }
+
+    pub fn is_white(&self, p: Point) -> bool {
+        self.panels[p]
+    }
+
+    /// Number of panels painted at least once.
+    pub fn painted_count(&self) -> usize {
+        self.history
+            .iter()
+            .map(|paint| paint.pos)
+            .collect::<std::collections::BTreeSet<Point>>()
+            .len()
+    }
+
+    /// Every panel painted, in order.
+    pub fn history(&self) -> &[Paint] {
+        &self.history
+    }
+
+    /// The top-left and bottom-right white panels, or None if nothing is
+    /// white.
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        bounds(self.panels.iter_points().filter(|p| self.panels[*p]))
+    }
+
+    /// Draw the white panels as `#` and black as `.`, cropped to the white
+    /// panels.
+    pub fn to_ascii(&self) -> String {
+        let mut s = String::new();
+        if let Some((min, max)) = self.bounds() {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    s.push(if self.is_white(point(x, y)) { '#' } else { '.' });
+                }
+                s.push('\n');
+            }
+        }
+        s
+    }
+
+    /// Draw as a plain PBM bitmap, cropped to the white panels.
+    ///
+    /// In PBM, 1 is black, so the hull looks as painted.
+    pub fn to_pbm(&self) -> String {
+        let (min, max) = self.bounds().unwrap_or((point(0, 0), point(0, 0)));
+        let mut s = format!("P1\n{} {}\n", max.x - min.x + 1, max.y - min.y + 1);
+        for y in min.y..=max.y {
+            let row: Vec<&str> = (min.x..=max.x)
+                .map(|x| if self.is_white(point(x, y)) { "0" } else { "1" })
+                .collect();
+            s.push_str(&row.join(" "));
+            s.push('\n');
+        }
+        s
+    }
+
+    /// Draw as a plain PGM graymap, cropped to the white panels.
+    pub fn to_pgm(&self) -> String {
+        let (min, max) = self.bounds().unwrap_or((point(0, 0), point(0, 0)));
+        pgm(min, max, |p| if self.is_white(p) { 255 } else { 0 })
+    }
+
+    /// Write one PGM frame per painting step into `dir`, named
+    /// `frame-00000.pgm` and so on, showing the robot in gray.
+    ///
+    /// All frames cover every panel the robot visits, so they line up.
+    /// Returns the number of frames written.
+    pub fn save_animation(&self, dir: &Path) -> io::Result<usize> {
+        fs::create_dir_all(dir)?;
+        let (min, max) = match bounds(self.history.iter().map(|paint| paint.pos)) {
+            Some(b) => b,
+            None => return Ok(0),
+        };
+        let mut panels = GrowingMatrix::new(false);
+        panels[point(0, 0)] = self.start_white;
+        for (i, paint) in self.history.iter().enumerate() {
+            panels[paint.pos] = paint.white;
+            let robot = self.history.get(i + 1).map(|next| next.pos);
+            let frame = pgm(min, max, |p| {
+                if Some(p) == robot {
+                    ROBOT_GRAY
+                } else if panels[p] {
+                    255
+                } else {
+                    0
+                }
+            });
+            fs::write(dir.join(format!("frame-{:05}.pgm", i)), frame)?;
+        }
+        Ok(self.history.len())
+    }
+}
+
+/// The top-left and bottom-right corners of some points.
+fn bounds<I: Iterator<Item = Point>>(points: I) -> Option<(Point, Point)> {
+    points.fold(None, |acc, p| match acc {
+        None => Some((p, p)),
+        Some((min, max)) => Some((
+            point(min.x.min(p.x), min.y.min(p.y)),
+            point(max.x.max(p.x), max.y.max(p.y)),
+        )),
+    })
+}
+
+fn pgm<F: Fn(Point) -> u8>(min: Point, max: Point, gray: F) -> String {
+    let mut s = format!("P2\n{} {}\n255\n", max.x - min.x + 1, max.y - min.y + 1);
+    for y in min.y..=max.y {
+        let row: Vec<String> = (min.x..=max.x)
+            .map(|x| gray(point(x, y)).to_string())
+            .collect();
+        s.push_str(&row.join(" "));
+        s.push('\n');
+    }
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn paint_from_black() {
+        let mut robot = HullPainter::from_file("input/input11.txt", false);
+        robot.run();
+        assert_eq!(robot.painted_count(), 1709);
+        assert!(robot.history().len() > robot.painted_count());
+    }
+
+    #[test]
+    fn paint_registration() {
+        let mut robot = HullPainter::from_file("input/input11.txt", true);
+        robot.run();
+        let ascii = robot.to_ascii();
+        let lines: Vec<&str> = ascii.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "###...##..#..#.####.#..#..##....##.#..#");
+
+        let pbm = robot.to_pbm();
+        assert!(pbm.starts_with("P1\n39 6\n0 0 0 1 1 1 0 0 1 "));
+        let pgm = robot.to_pgm();
+        assert!(pgm.starts_with("P2\n39 6\n255\n255 255 255 0 0 0 255 "));
+    }
+
+    #[test]
+    fn save_frames() {
+        let mut robot = HullPainter::from_file("input/input11.txt", true);
+        robot.run();
+        let dir =
+            std::env::temp_dir().join(format!("aoc2019-hull-painter-test-{}", std::process::id()));
+        assert_eq!(robot.save_animation(&dir).unwrap(), robot.history().len());
+        let first = fs::read_to_string(dir.join("frame-00000.pgm")).unwrap();
+        assert!(first.starts_with("P2\n"));
+        assert!(first.contains(" 128"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
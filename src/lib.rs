@@ -12,11 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod breakout;
+pub mod brute_force;
+pub mod direction;
+pub mod explore;
+pub mod growing_matrix;
+pub mod hull_painter;
 pub mod intcode;
 pub mod matrix;
+pub mod maze_graph;
+pub mod ocr;
 pub mod permute;
-mod point;
+pub mod point;
+pub mod point_n;
 pub mod shortest_path;
+pub mod space_image;
+pub mod sparse_grid;
+pub mod springdroid;
 
 pub use matrix::Matrix;
 pub use point::{point, Point};
@@ -15,6 +15,7 @@
 //! A rectangular 2d matrix.
 //!
 //! Matrices are indexed by (row, column) coordinates.
+use std::collections::VecDeque;
 use std::ops::{Index, IndexMut};
 
 use crate::{point, Point};
@@ -124,6 +125,290 @@ impl<T: Clone> Matrix<T> {
     }
 }
 
+impl<T: Clone> Matrix<T> {
+    /// Make a matrix by calling `f` for every point, in row-major order.
+    fn from_fn<F: FnMut(Point) -> T>(w: usize, h: usize, mut f: F) -> Matrix<T> {
+        let mut d = Vec::with_capacity(w * h);
+        for y in 0..h {
+            for x in 0..w {
+                d.push(f(point(x as isize, y as isize)));
+            }
+        }
+        Matrix {
+            w: w as isize,
+            h: h as isize,
+            d,
+        }
+    }
+
+    /// Swap rows and columns.
+    pub fn transpose(&self) -> Matrix<T> {
+        Matrix::from_fn(self.height(), self.width(), |p| {
+            self[point(p.y, p.x)].clone()
+        })
+    }
+
+    /// Rotate 90 degrees clockwise.
+    pub fn rotate_cw(&self) -> Matrix<T> {
+        Matrix::from_fn(self.height(), self.width(), |p| {
+            self[point(p.y, self.h - 1 - p.x)].clone()
+        })
+    }
+
+    /// Rotate 90 degrees counter-clockwise.
+    pub fn rotate_ccw(&self) -> Matrix<T> {
+        Matrix::from_fn(self.height(), self.width(), |p| {
+            self[point(self.w - 1 - p.y, p.x)].clone()
+        })
+    }
+
+    pub fn rotate_180(&self) -> Matrix<T> {
+        let mut d = self.d.clone();
+        d.reverse();
+        Matrix { d, ..*self }
+    }
+
+    /// Rotate clockwise by a number of quarter turns.
+    pub fn rotate(&self, quarter_turns: usize) -> Matrix<T> {
+        match quarter_turns % 4 {
+            0 => self.clone(),
+            1 => self.rotate_cw(),
+            2 => self.rotate_180(),
+            _ => self.rotate_ccw(),
+        }
+    }
+
+    /// Mirror left to right.
+    pub fn flip_horizontal(&self) -> Matrix<T> {
+        Matrix::from_fn(self.width(), self.height(), |p| {
+            self[point(self.w - 1 - p.x, p.y)].clone()
+        })
+    }
+
+    /// Mirror top to bottom.
+    pub fn flip_vertical(&self) -> Matrix<T> {
+        Matrix::from_fn(self.width(), self.height(), |p| {
+            self[point(p.x, self.h - 1 - p.y)].clone()
+        })
+    }
+
+    /// Borrow a `w` by `h` rectangle with its top-left corner at `origin`,
+    /// which must be entirely within this matrix.
+    pub fn view(&self, origin: Point, w: usize, h: usize) -> MatrixView<'_, T> {
+        assert!(origin.x >= 0 && origin.y >= 0, "{:?} out of range", origin);
+        assert!(
+            origin.x + w as isize <= self.w && origin.y + h as isize <= self.h,
+            "{}x{} at {:?} doesn't fit in {}x{}",
+            w,
+            h,
+            origin,
+            self.w,
+            self.h
+        );
+        MatrixView {
+            m: self,
+            origin,
+            w,
+            h,
+        }
+    }
+
+    /// Copy a `w` by `h` rectangle with its top-left corner at `origin`.
+    pub fn crop(&self, origin: Point, w: usize, h: usize) -> Matrix<T> {
+        self.view(origin, w, h).to_matrix()
+    }
+
+    /// Copy all of `other` into this matrix, with its top-left corner at
+    /// `at`. It must fit entirely.
+    pub fn paste(&mut self, other: &Matrix<T>, at: Point) {
+        assert!(
+            at.x >= 0 && at.y >= 0 && at.x + other.w <= self.w && at.y + other.h <= self.h,
+            "{}x{} at {:?} doesn't fit in {}x{}",
+            other.w,
+            other.h,
+            at,
+            self.w,
+            self.h
+        );
+        for p in other.iter_points() {
+            self[point(p.x + at.x, p.y + at.y)] = other[p].clone();
+        }
+    }
+}
+
+/// Which cells count as touching, for flood fills and components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Up, down, left and right.
+    Four,
+    /// Also diagonally.
+    Eight,
+}
+
+/// Statistics about one connected region of a matrix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Number of cells.
+    pub area: usize,
+    /// Number of cell edges that face a cell outside the region, or the
+    /// edge of the matrix.
+    pub perimeter: usize,
+    /// Top-left corner of the bounding box.
+    pub min: Point,
+    /// Bottom-right corner of the bounding box.
+    pub max: Point,
+}
+
+impl<T: Clone> Matrix<T> {
+    fn contains_point(&self, p: Point) -> bool {
+        p.x >= 0 && p.y >= 0 && p.x < self.w && p.y < self.h
+    }
+
+    /// Neighbors of `p` that are within the matrix.
+    fn connected(&self, p: Point, conn: Connectivity) -> Vec<Point> {
+        let mut v = p.neighbors();
+        if conn == Connectivity::Eight {
+            v.extend_from_slice(&[
+                p.left().up(),
+                p.right().up(),
+                p.left().down(),
+                p.right().down(),
+            ]);
+        }
+        v.retain(|np| self.contains_point(*np));
+        v
+    }
+
+    /// Spread out from `seed` through 4-connected cells that satisfy
+    /// `passable`, and return the number of steps to reach each cell, or
+    /// None for cells that can't be reached.
+    ///
+    /// The seed itself is always reached, at distance 0.
+    pub fn flood_fill<F: Fn(&T) -> bool>(&self, seed: Point, passable: F) -> Matrix<Option<usize>> {
+        let mut dist = Matrix::new(self.width(), self.height(), None);
+        dist[seed] = Some(0);
+        let mut queue = VecDeque::new();
+        queue.push_back(seed);
+        while let Some(p) = queue.pop_front() {
+            let d = dist[p].unwrap();
+            for np in self.connected(p, Connectivity::Four) {
+                if dist[np].is_none() && passable(&self[np]) {
+                    dist[np] = Some(d + 1);
+                    queue.push_back(np);
+                }
+            }
+        }
+        dist
+    }
+
+    /// Label the connected regions of cells that satisfy `include`.
+    ///
+    /// Returns a matrix with the index of each cell's region, or None for
+    /// cells that aren't included, and statistics for each region. Regions
+    /// are numbered in row-major order of their first cell.
+    pub fn components<F: Fn(&T) -> bool>(
+        &self,
+        include: F,
+        conn: Connectivity,
+    ) -> (Matrix<Option<usize>>, Vec<Region>) {
+        let mut labels = Matrix::new(self.width(), self.height(), None);
+        let mut regions = Vec::new();
+        for start in self.iter_points() {
+            if labels[start].is_some() || !include(&self[start]) {
+                continue;
+            }
+            let label = regions.len();
+            labels[start] = Some(label);
+            let mut region = Region {
+                area: 0,
+                perimeter: 0,
+                min: start,
+                max: start,
+            };
+            let mut stack = vec![start];
+            while let Some(p) = stack.pop() {
+                region.area += 1;
+                region.min = point(region.min.x.min(p.x), region.min.y.min(p.y));
+                region.max = point(region.max.x.max(p.x), region.max.y.max(p.y));
+                for np in self.connected(p, conn) {
+                    if labels[np].is_none() && include(&self[np]) {
+                        labels[np] = Some(label);
+                        stack.push(np);
+                    }
+                }
+            }
+            regions.push(region);
+        }
+        for p in self.iter_points() {
+            if let Some(label) = labels[p] {
+                regions[label].perimeter += p
+                    .neighbors()
+                    .into_iter()
+                    .filter(|np| !self.contains_point(*np) || labels[*np] != Some(label))
+                    .count();
+            }
+        }
+        (labels, regions)
+    }
+}
+
+/// A borrowed rectangle within a `Matrix`, indexed from its own top-left
+/// corner.
+#[derive(Clone, Copy)]
+pub struct MatrixView<'a, T> {
+    m: &'a Matrix<T>,
+    origin: Point,
+    w: usize,
+    h: usize,
+}
+
+impl<'a, T: Clone> MatrixView<'a, T> {
+    pub fn width(&self) -> usize {
+        self.w
+    }
+
+    pub fn height(&self) -> usize {
+        self.h
+    }
+
+    /// Position of this view's top-left corner in the underlying matrix.
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+
+    pub fn try_get(&self, p: Point) -> Option<T> {
+        if p.x >= 0 && p.y >= 0 && p.x < self.w as isize && p.y < self.h as isize {
+            Some(self[p].clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn iter_points(&self) -> impl Iterator<Item = Point> {
+        let (w, h) = (self.w as isize, self.h as isize);
+        (0..h).flat_map(move |y| (0..w).map(move |x| point(x, y)))
+    }
+
+    /// Copy into a new matrix.
+    pub fn to_matrix(&self) -> Matrix<T> {
+        Matrix::from_fn(self.w, self.h, |p| self[p].clone())
+    }
+}
+
+impl<'a, T: Clone> Index<Point> for MatrixView<'a, T> {
+    type Output = T;
+    fn index(&self, p: Point) -> &T {
+        assert!(
+            p.x >= 0 && p.y >= 0 && p.x < self.w as isize && p.y < self.h as isize,
+            "{:?} out of range for {}x{} view",
+            p,
+            self.w,
+            self.h
+        );
+        &self.m[point(p.x + self.origin.x, p.y + self.origin.y)]
+    }
+}
+
 impl Matrix<char> {
     /// Build a matrix from a string containing multiple lines.
     ///
@@ -232,4 +517,92 @@ mod test {
         assert_eq!(matrix.width(), 107);
         assert_eq!(matrix.height(), 109);
     }
+
+    #[test]
+    fn rotate_and_flip() {
+        let m = Matrix::from_string_lines("abc\ndef\n");
+        assert_eq!(m.transpose().to_string_lines(), "ad\nbe\ncf\n");
+        assert_eq!(m.rotate_cw().to_string_lines(), "da\neb\nfc\n");
+        assert_eq!(m.rotate_ccw().to_string_lines(), "cf\nbe\nad\n");
+        assert_eq!(m.rotate_180().to_string_lines(), "fed\ncba\n");
+        assert_eq!(m.flip_horizontal().to_string_lines(), "cba\nfed\n");
+        assert_eq!(m.flip_vertical().to_string_lines(), "def\nabc\n");
+        for turns in 0..8 {
+            assert!(m.rotate(turns).rotate(4 - turns % 4) == m);
+        }
+        assert!(m.rotate(3) == m.rotate_ccw());
+        assert!(m.rotate_cw().rotate_cw() == m.rotate_180());
+        assert!(m.transpose().transpose() == m);
+        assert!(m.flip_horizontal().flip_vertical() == m.rotate_180());
+    }
+
+    #[test]
+    fn views_crop_and_paste() {
+        let m = Matrix::from_string_lines("abcd\nefgh\nijkl\n");
+        let v = m.view(point(1, 1), 3, 2);
+        assert_eq!((v.width(), v.height()), (3, 2));
+        assert_eq!(v[point(0, 0)], 'f');
+        assert_eq!(v[point(2, 1)], 'l');
+        assert_eq!(v.try_get(point(3, 0)), None);
+        assert_eq!(v.iter_points().count(), 6);
+        assert_eq!(v.to_matrix().to_string_lines(), "fgh\njkl\n");
+        assert_eq!(m.crop(point(0, 0), 2, 3).to_string_lines(), "ab\nef\nij\n");
+
+        let mut big = Matrix::new(5, 2, '.');
+        big.paste(&m.crop(point(2, 0), 2, 2), point(3, 0));
+        assert_eq!(big.to_string_lines(), "...cd\n...gh\n");
+    }
+
+    #[test]
+    #[should_panic]
+    fn view_out_of_range() {
+        Matrix::new(3, 3, 0).view(point(2, 0), 2, 1);
+    }
+
+    #[test]
+    fn flood_around_walls() {
+        let m = Matrix::from_string_lines("..#.\n#.#.\n....\n###.\n");
+        let dist = m.flood_fill(point(0, 0), |c| *c == '.');
+        assert_eq!(dist[point(0, 0)], Some(0));
+        assert_eq!(dist[point(3, 0)], Some(7));
+        assert_eq!(dist[point(3, 3)], Some(6));
+        assert_eq!(dist[point(2, 0)], None);
+        assert_eq!(dist.values().flatten().max(), Some(&7));
+    }
+
+    #[test]
+    fn label_components() {
+        let m = Matrix::from_string_lines("#..#\n.#..\n...#\n##.#\n");
+        let (labels, regions) = m.components(|c| *c == '#', Connectivity::Four);
+        assert_eq!(regions.len(), 5);
+        assert_eq!(labels[point(0, 0)], Some(0));
+        assert_eq!(labels[point(1, 0)], None);
+        assert_eq!(labels[point(3, 3)], labels[point(3, 2)]);
+        assert_eq!(
+            regions[2],
+            Region {
+                area: 1,
+                perimeter: 4,
+                min: point(1, 1),
+                max: point(1, 1),
+            }
+        );
+        assert_eq!(
+            regions[3],
+            Region {
+                area: 2,
+                perimeter: 6,
+                min: point(3, 2),
+                max: point(3, 3),
+            }
+        );
+
+        let (labels, regions) = m.components(|c| *c == '#', Connectivity::Eight);
+        assert_eq!(regions.len(), 4);
+        assert_eq!(labels[point(1, 1)], Some(0));
+        assert_eq!(regions[0].area, 2);
+        assert_eq!(regions[0].min, point(0, 0));
+        assert_eq!(regions[0].max, point(1, 1));
+        assert_eq!(regions.iter().map(|r| r.area).sum::<usize>(), 7);
+    }
 }
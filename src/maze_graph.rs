@@ -0,0 +1,262 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contract a maze drawn in a character matrix into a weighted graph.
+//!
+//! The nodes are points of interest, such as keys, portals or junctions,
+//! and each edge is the length of the corridor between two nodes that
+//! doesn't pass through any other node. Searching this graph is much faster
+//! than stepping through the maze one square at a time.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::shortest_path::distances_from;
+use crate::{Matrix, Point};
+
+/// A corridor from one node to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub to: Point,
+    /// Number of steps.
+    pub dist: usize,
+    /// Doors passed through along the way, in order.
+    pub doors: Vec<char>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MazeGraph {
+    /// The character at every node.
+    pub nodes: BTreeMap<Point, char>,
+    /// Edges leaving every node, including nodes with no edges.
+    pub edges: BTreeMap<Point, Vec<Edge>>,
+}
+
+/// Build a graph from a maze.
+///
+/// `is_open` says which characters can be walked through, including nodes
+/// and doors. `is_node` picks the points of interest. `is_door` picks open
+/// squares that are recorded on the edges that pass through them.
+///
+/// There may be several edges between the same two nodes, when a longer
+/// corridor avoids some of the doors on a shorter one. Every edge is kept
+/// unless another edge between the same nodes is no longer and needs no
+/// doors it doesn't. Among equally good corridors, the doors are from one
+/// of them.
+pub fn contract<O, N, D>(m: &Matrix<char>, is_open: O, is_node: N, is_door: D) -> MazeGraph
+where
+    O: Fn(char) -> bool,
+    N: Fn(Point, char) -> bool,
+    D: Fn(char) -> bool,
+{
+    let mut graph = MazeGraph::default();
+    for p in m.iter_points() {
+        let c = m[p];
+        if is_open(c) && is_node(p, c) {
+            graph.nodes.insert(p, c);
+        }
+    }
+    for &start in graph.nodes.keys() {
+        let mut edges: Vec<Edge> = Vec::new();
+        // The sets of doors already seen on the way to every square. Since
+        // the search is breadth-first, those routes were no longer, so a
+        // route needing all the same doors can be dropped.
+        let mut seen: Matrix<Vec<Vec<char>>> = Matrix::new(m.width(), m.height(), Vec::new());
+        seen[start].push(Vec::new());
+        let mut queue = VecDeque::new();
+        queue.push_back((start, 0, Vec::new()));
+        while let Some((p, dist, doors)) = queue.pop_front() {
+            for (np, &c) in m.neighbors4(p) {
+                if np == start || !is_open(c) {
+                    continue;
+                }
+                let mut doors = doors.clone();
+                if is_door(c) && !doors.contains(&c) {
+                    doors.push(c);
+                }
+                if seen[np].iter().any(|s| is_subset(s, &doors)) {
+                    continue;
+                }
+                seen[np].push(doors.clone());
+                if graph.nodes.contains_key(&np) {
+                    edges.push(Edge {
+                        to: np,
+                        dist: dist + 1,
+                        doors,
+                    });
+                } else {
+                    queue.push_back((np, dist + 1, doors));
+                }
+            }
+        }
+        graph.edges.insert(start, edges);
+    }
+    graph
+}
+
+fn is_subset(a: &[char], b: &[char]) -> bool {
+    a.iter().all(|x| b.contains(x))
+}
+
+/// True if `p` is open and has at least three open neighbors, so that
+/// corridors meet there.
+pub fn is_junction<O: Fn(char) -> bool>(m: &Matrix<char>, p: Point, is_open: O) -> bool {
+    is_open(m[p]) && m.neighbors4(p).iter().filter(|(_, c)| is_open(**c)).count() >= 3
+}
+
+impl MazeGraph {
+    pub fn edges_from(&self, p: Point) -> &[Edge] {
+        self.edges.get(&p).map_or(&[], Vec::as_slice)
+    }
+
+    /// Shortest distance from `from` to every node that can be reached.
+    ///
+    /// Only edges for which `can_use` is true are followed. The search
+    /// doesn't continue onwards from nodes where `pass_through` is false,
+    /// although they are reached.
+    pub fn distances_from<U, T>(
+        &self,
+        from: Point,
+        can_use: U,
+        pass_through: T,
+    ) -> BTreeMap<Point, usize>
+    where
+        U: Fn(&Edge) -> bool,
+        T: Fn(Point, char) -> bool,
+    {
+        let mut nbrs = |p: Point| -> Vec<(Point, usize)> {
+            if p != from && !pass_through(p, self.nodes[&p]) {
+                return Vec::new();
+            }
+            self.edges_from(p)
+                .iter()
+                .filter(|edge| can_use(edge))
+                .map(|edge| (edge.to, edge.dist))
+                .collect()
+        };
+        distances_from(from, &mut nbrs).into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::point;
+
+    const MAZE: &str = "\
+#########
+#b.A.@.a#
+#.#####.#
+#...c...#
+#########
+";
+
+    fn graph() -> MazeGraph {
+        contract(
+            &Matrix::from_string_lines(MAZE),
+            |c| c != '#',
+            |_, c| c == '@' || c.is_ascii_lowercase(),
+            |c| c.is_ascii_uppercase(),
+        )
+    }
+
+    #[test]
+    fn contract_maze() {
+        let g = graph();
+        assert_eq!(g.nodes.len(), 4);
+        assert_eq!(g.nodes[&point(5, 1)], '@');
+        assert_eq!(
+            g.edges_from(point(5, 1)),
+            &[
+                Edge {
+                    to: point(7, 1),
+                    dist: 2,
+                    doors: vec![],
+                },
+                Edge {
+                    to: point(1, 1),
+                    dist: 4,
+                    doors: vec!['A'],
+                },
+            ]
+        );
+        let b = g.edges_from(point(1, 1));
+        assert_eq!(b.len(), 2);
+        assert!(b.contains(&Edge {
+            to: point(4, 3),
+            dist: 5,
+            doors: vec![],
+        }));
+    }
+
+    #[test]
+    fn distances() {
+        let g = graph();
+        let no_doors = g.distances_from(point(5, 1), |e| e.doors.is_empty(), |_, _| true);
+        assert_eq!(no_doors[&point(7, 1)], 2);
+        assert_eq!(no_doors[&point(4, 3)], 7);
+        assert_eq!(no_doors[&point(1, 1)], 12);
+
+        let stop_at_keys = g.distances_from(point(5, 1), |e| e.doors.is_empty(), |_, c| c == '@');
+        assert_eq!(stop_at_keys.len(), 2);
+        assert_eq!(stop_at_keys[&point(7, 1)], 2);
+
+        let any = g.distances_from(point(5, 1), |_| true, |_, _| true);
+        assert_eq!(any[&point(1, 1)], 4);
+    }
+
+    #[test]
+    fn longer_corridor_without_doors() {
+        let m = Matrix::from_string_lines(
+            "\
+#######
+#@.A.a#
+#.###.#
+#.....#
+#######
+",
+        );
+        let g = contract(
+            &m,
+            |c| c != '#',
+            |_, c| c == '@' || c.is_ascii_lowercase(),
+            |c| c.is_ascii_uppercase(),
+        );
+        assert_eq!(
+            g.edges_from(point(1, 1)),
+            &[
+                Edge {
+                    to: point(5, 1),
+                    dist: 4,
+                    doors: vec!['A'],
+                },
+                Edge {
+                    to: point(5, 1),
+                    dist: 8,
+                    doors: vec![],
+                },
+            ]
+        );
+        let no_doors = g.distances_from(point(1, 1), |e| e.doors.is_empty(), |_, _| true);
+        assert_eq!(no_doors[&point(5, 1)], 8);
+        let any = g.distances_from(point(1, 1), |_| true, |_, _| true);
+        assert_eq!(any[&point(5, 1)], 4);
+    }
+
+    #[test]
+    fn junctions() {
+        let m = Matrix::from_string_lines("#.#\n...\n#.#\n");
+        assert!(is_junction(&m, point(1, 1), |c| c == '.'));
+        assert!(!is_junction(&m, point(1, 0), |c| c == '.'));
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read the block letters that some puzzles draw as their answer.
+//!
+//! Two fonts are known: letters 4 pixels wide and 6 high (as on days 8 and
+//! 11), and 6 wide and 10 high. The font is chosen from the height of the
+//! picture, after trimming blank rows.
+//!
+//! Letters don't need to be on any particular pitch. At each lit column the
+//! widest letter that fits is taken, so a letter can touch the next one,
+//! as the 5-wide `Y` does in the small font.
+
+use std::fmt;
+
+use crate::{point, Matrix};
+
+/// Letters 4 wide and 6 high, with `#` for lit pixels.
+const FONT_4X6: &[(char, &str)] = &[
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', "###\n.#.\n.#.\n.#.\n.#.\n###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+/// Letters 6 wide and 10 high, with `#` for lit pixels.
+const FONT_6X10: &[(char, &str)] = &[
+    (
+        'A',
+        "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'B',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####.",
+    ),
+    (
+        'C',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####.",
+    ),
+    (
+        'E',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'F',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'G',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#",
+    ),
+    (
+        'H',
+        "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'J',
+        "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n#...#.\n.###..",
+    ),
+    (
+        'K',
+        "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#",
+    ),
+    (
+        'L',
+        "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'N',
+        "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#",
+    ),
+    (
+        'P',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'R',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#",
+    ),
+    (
+        'X',
+        "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#",
+    ),
+    (
+        'Z',
+        "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######",
+    ),
+];
+
+/// Some glyphs in the picture weren't recognized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcrError {
+    /// Height of the picture, after trimming blank rows.
+    pub height: usize,
+    /// The first and last column of each unrecognized glyph, in the
+    /// untrimmed picture.
+    pub unrecognized: Vec<(usize, usize)>,
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if font_for_height(self.height).is_none() {
+            return write!(f, "no font is {} pixels high", self.height);
+        }
+        write!(f, "unrecognized glyphs in columns")?;
+        for (a, b) in &self.unrecognized {
+            write!(f, " {}..={}", a, b)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+fn font_for_height(h: usize) -> Option<&'static [(char, &'static str)]> {
+    match h {
+        6 => Some(FONT_4X6),
+        10 => Some(FONT_6X10),
+        _ => None,
+    }
+}
+
+/// Read the letters in a picture where true is a lit pixel.
+pub fn read_matrix(m: &Matrix<bool>) -> Result<String, OcrError> {
+    let rows: Vec<Vec<bool>> = (0..m.height())
+        .map(|y| {
+            (0..m.width())
+                .map(|x| m[point(x as isize, y as isize)])
+                .collect()
+        })
+        .collect();
+    read_rows(&rows)
+}
+
+/// Read the letters in a picture of characters, where space and `.` are
+/// unlit and anything else is lit.
+pub fn read_chars(m: &Matrix<char>) -> Result<String, OcrError> {
+    let rows: Vec<Vec<bool>> = (0..m.height())
+        .map(|y| {
+            (0..m.width())
+                .map(|x| is_lit(m[point(x as isize, y as isize)]))
+                .collect()
+        })
+        .collect();
+    read_rows(&rows)
+}
+
+/// Read the letters drawn in a multi-line string, where space and `.` are
+/// unlit and anything else is lit.
+///
+/// Lines can be different lengths, for example if trailing spaces were
+/// stripped.
+pub fn read_str(s: &str) -> Result<String, OcrError> {
+    let rows: Vec<Vec<bool>> = s.lines().map(|l| l.chars().map(is_lit).collect()).collect();
+    read_rows(&rows)
+}
+
+fn is_lit(c: char) -> bool {
+    c != ' ' && c != '.'
+}
+
+fn read_rows(rows: &[Vec<bool>]) -> Result<String, OcrError> {
+    let lit_row = |r: &Vec<bool>| r.iter().any(|b| *b);
+    let top = rows.iter().position(lit_row).unwrap_or(0);
+    let bottom = rows.iter().rposition(lit_row).map_or(0, |b| b + 1);
+    let rows = &rows[top..bottom.max(top)];
+    let height = rows.len();
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let lit = |x: usize, y: usize| rows[y].get(x).copied().unwrap_or_default();
+    let font = match font_for_height(height) {
+        Some(font) => font,
+        None => {
+            return Err(OcrError {
+                height,
+                unrecognized: Vec::new(),
+            })
+        }
+    };
+
+    let mut text = String::new();
+    let mut unrecognized = Vec::new();
+    let mut x = 0;
+    while x < width {
+        if !(0..height).any(|y| lit(x, y)) {
+            x += 1;
+            continue;
+        }
+        let fits = |glyph: &str| {
+            glyph.lines().enumerate().all(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .all(|(gx, c)| lit(x + gx, y) == (c == '#'))
+            })
+        };
+        let glyph_width = |glyph: &str| glyph.find('\n').unwrap_or(glyph.len());
+        match font
+            .iter()
+            .filter(|(_, g)| fits(g))
+            .max_by_key(|(_, g)| glyph_width(g))
+        {
+            Some((c, g)) => {
+                text.push(*c);
+                x += glyph_width(g);
+            }
+            None => {
+                // Skip to the next blank column, and carry on from there.
+                let start = x;
+                while x < width && (0..height).any(|y| lit(x, y)) {
+                    x += 1;
+                }
+                unrecognized.push((start, x - 1));
+            }
+        }
+    }
+    if unrecognized.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError {
+            height,
+            unrecognized,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Draw a word in a font, with blank columns between letters.
+    fn draw(font: &[(char, &str)], word: &str, gap: usize) -> String {
+        let glyphs: Vec<Vec<&str>> = word
+            .chars()
+            .map(|c| {
+                font.iter()
+                    .find(|(fc, _)| *fc == c)
+                    .unwrap()
+                    .1
+                    .lines()
+                    .collect()
+            })
+            .collect();
+        let mut s = String::new();
+        for y in 0..glyphs[0].len() {
+            let row: Vec<&str> = glyphs.iter().map(|g| g[y]).collect();
+            s.push_str(&row.join(&".".repeat(gap)));
+            s.push('\n');
+        }
+        s
+    }
+
+    #[test]
+    fn read_small_font() {
+        let all: String = FONT_4X6.iter().map(|(c, _)| *c).collect();
+        assert_eq!(read_str(&draw(FONT_4X6, &all, 1)), Ok(all));
+    }
+
+    #[test]
+    fn read_large_font() {
+        let all: String = FONT_6X10.iter().map(|(c, _)| *c).collect();
+        let drawn = format!("\n{}\n", draw(FONT_6X10, &all, 2));
+        assert_eq!(read_chars(&Matrix::from_string_lines(&drawn)), Ok(all));
+    }
+
+    #[test]
+    fn read_bool_matrix() {
+        let chars = Matrix::from_string_lines(&draw(FONT_4X6, "HI", 1));
+        let mut m = Matrix::new(chars.width(), chars.height(), false);
+        for p in chars.iter_points() {
+            m[p] = chars[p] == '#';
+        }
+        assert_eq!(read_matrix(&m), Ok("HI".to_owned()));
+    }
+
+    /// In real output the small font is on a 5 column pitch, so `Y` runs
+    /// straight into the next letter.
+    #[test]
+    fn touching_letters() {
+        assert_eq!(read_str(&draw(FONT_4X6, "YZ", 0)), Ok("YZ".to_owned()));
+        assert_eq!(read_str(&draw(FONT_4X6, "YH", 0)), Ok("YH".to_owned()));
+    }
+
+    #[test]
+    fn unrecognized_glyphs() {
+        let mut rows: Vec<String> = draw(FONT_4X6, "AB", 2).lines().map(String::from).collect();
+        rows[1].replace_range(2..3, "#");
+        let err = read_str(&rows.join("\n")).unwrap_err();
+        assert_eq!(err.unrecognized, vec![(0, 3)]);
+        assert_eq!(err.to_string(), "unrecognized glyphs in columns 0..=3");
+
+        let err = read_str("#\n#\n#\n").unwrap_err();
+        assert_eq!(err.to_string(), "no font is 3 pixels high");
+    }
+}
@@ -12,10 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! Permutations, combinations, subsets and products of lists.
+//!
+//! `permutations` builds all of them at once, which is fine for a handful
+//! of elements. `Permutations` produces them one at a time, in
+//! lexicographic order of their positions in the input, but allocates a
+//! new `Vec` for each. To avoid allocating at all, `next_permutation`
+//! steps a slice in place, and `for_each_permutation` calls a closure on
+//! every arrangement of a slice.
+//!
+//! The other iterators here are lazy too, yielding a new `Vec` each time.
+
 use std::iter::FromIterator;
 use std::iter::IntoIterator;
 
 /// Generate all permutations of the elements in l.
+///
+/// An empty input gives no permutations.
 pub fn permutations<T, I>(l: I) -> Vec<Vec<T>>
 where
     T: Clone,
@@ -44,6 +57,272 @@ where
     }
 }
 
+/// Rearrange `v` into the next permutation in lexicographic order.
+///
+/// Returns false, leaving `v` sorted, if it was already the last
+/// permutation. Starting from a sorted slice and calling this until it
+/// returns false visits every distinct permutation once, even if some
+/// elements are equal.
+pub fn next_permutation<T: Ord>(v: &mut [T]) -> bool {
+    // Find the longest non-increasing suffix; the element before it is the
+    // pivot.
+    let mut i = v.len();
+    while i > 1 && v[i - 2] >= v[i - 1] {
+        i -= 1;
+    }
+    if i <= 1 {
+        v.reverse();
+        return false;
+    }
+    let pivot = i - 2;
+    // Swap the pivot with the rightmost element greater than it, and put the
+    // suffix in increasing order.
+    let mut j = v.len() - 1;
+    while v[j] <= v[pivot] {
+        j -= 1;
+    }
+    v.swap(pivot, j);
+    v[pivot + 1..].reverse();
+    true
+}
+
+/// Call `f` with every distinct permutation of `v`, in lexicographic
+/// order, rearranging `v` in place rather than allocating.
+///
+/// `v` is sorted first, and is left sorted afterwards.
+pub fn for_each_permutation<T, F>(v: &mut [T], mut f: F)
+where
+    T: Ord,
+    F: FnMut(&[T]),
+{
+    v.sort();
+    loop {
+        f(v);
+        if !next_permutation(v) {
+            return;
+        }
+    }
+}
+
+/// The number of permutations of `n` elements, or None if it overflows.
+pub fn permutations_len(n: usize) -> Option<usize> {
+    (1..=n).try_fold(1usize, |acc, i| acc.checked_mul(i))
+}
+
+/// An iterator over every permutation of some elements, produced lazily.
+///
+/// Permutations come in lexicographic order of the elements' positions in
+/// the input, so sorted input gives sorted output. Elements are treated
+/// as distinct even if they're equal, so there are always `n!`
+/// permutations. Unlike `permutations`, an empty input gives one empty
+/// permutation.
+///
+/// Each permutation is a newly allocated `Vec`; see `for_each_permutation`
+/// to avoid that.
+pub struct Permutations<T> {
+    items: Vec<T>,
+    /// The next permutation, as positions in `items`, or None when
+    /// finished.
+    order: Option<Vec<usize>>,
+}
+
+impl<T: Clone> Permutations<T> {
+    pub fn new<I: IntoIterator<Item = T>>(items: I) -> Permutations<T> {
+        let items: Vec<T> = items.into_iter().collect();
+        let order = Some((0..items.len()).collect());
+        Permutations { items, order }
+    }
+}
+
+impl<T: Clone> Iterator for Permutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        let items = &self.items;
+        let order = self.order.as_mut()?;
+        let perm = order.iter().map(|&i| items[i].clone()).collect();
+        if !next_permutation(order) {
+            self.order = None;
+        }
+        Some(perm)
+    }
+}
+
+/// Every distinct permutation of a list that may contain repeated
+/// elements, in lexicographic order.
+pub struct MultisetPermutations<T> {
+    /// The next permutation, or None when finished.
+    next: Option<Vec<T>>,
+}
+
+impl<T: Ord + Clone> MultisetPermutations<T> {
+    pub fn new<I: IntoIterator<Item = T>>(items: I) -> MultisetPermutations<T> {
+        let mut v: Vec<T> = items.into_iter().collect();
+        v.sort();
+        MultisetPermutations { next: Some(v) }
+    }
+}
+
+impl<T: Ord + Clone> Iterator for MultisetPermutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        let v = self.next.as_mut()?;
+        let perm = v.clone();
+        if !next_permutation(v) {
+            self.next = None;
+        }
+        Some(perm)
+    }
+}
+
+/// Every way of choosing `k` of the elements, keeping them in their
+/// original order.
+///
+/// Combinations come in lexicographic order of the elements' positions.
+pub struct Combinations<T> {
+    items: Vec<T>,
+    /// Positions of the next combination, or None when finished.
+    chosen: Option<Vec<usize>>,
+}
+
+impl<T: Clone> Combinations<T> {
+    pub fn new<I: IntoIterator<Item = T>>(items: I, k: usize) -> Combinations<T> {
+        let items: Vec<T> = items.into_iter().collect();
+        let chosen = if k <= items.len() {
+            Some((0..k).collect())
+        } else {
+            None
+        };
+        Combinations { items, chosen }
+    }
+}
+
+impl<T: Clone> Iterator for Combinations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        let items = &self.items;
+        let chosen = self.chosen.as_mut()?;
+        let comb = chosen.iter().map(|&i| items[i].clone()).collect();
+        // Advance the rightmost position that can still move right, and put
+        // everything after it just after it.
+        let (n, k) = (items.len(), chosen.len());
+        match (0..k).rev().find(|&i| chosen[i] < n - k + i) {
+            Some(i) => {
+                chosen[i] += 1;
+                for j in (i + 1)..k {
+                    chosen[j] = chosen[j - 1] + 1;
+                }
+            }
+            None => self.chosen = None,
+        }
+        Some(comb)
+    }
+}
+
+/// The position of the element to add or remove at each step, to go
+/// through every subset of `n` elements changing only one each time,
+/// starting from the empty set.
+///
+/// This is the binary reflected Gray code, and there are `2^n - 1` steps.
+pub fn gray_code_flips(n: usize) -> impl Iterator<Item = usize> {
+    assert!(n < 64, "too many elements for a subset: {}", n);
+    (1..(1u64 << n)).map(|i| i.trailing_zeros() as usize)
+}
+
+/// Every subset of the elements, starting with the empty set, in
+/// Gray-code order, so that each differs from the one before by adding or
+/// removing one element.
+///
+/// Elements within each subset are in their original order.
+pub struct Subsets<T> {
+    items: Vec<T>,
+    /// Bit `i` is set if element `i` is in the next subset.
+    mask: u64,
+    /// Number of subsets produced so far.
+    step: u64,
+}
+
+impl<T: Clone> Subsets<T> {
+    pub fn new<I: IntoIterator<Item = T>>(items: I) -> Subsets<T> {
+        let items: Vec<T> = items.into_iter().collect();
+        assert!(
+            items.len() < 64,
+            "too many elements for a subset: {}",
+            items.len()
+        );
+        Subsets {
+            items,
+            mask: 0,
+            step: 0,
+        }
+    }
+}
+
+impl<T: Clone> Iterator for Subsets<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.step >= 1 << self.items.len() {
+            return None;
+        }
+        let subset = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.mask & (1 << i) != 0)
+            .map(|(_, x)| x.clone())
+            .collect();
+        self.step += 1;
+        self.mask ^= 1 << self.step.trailing_zeros();
+        Some(subset)
+    }
+}
+
+/// Every way of taking one element from each of several lists.
+///
+/// Like an odometer, the last list changes fastest. With no lists there's
+/// one empty product; if any list is empty there are none.
+pub struct CartesianProduct<T> {
+    lists: Vec<Vec<T>>,
+    /// Position in each list of the next product, or None when finished.
+    pos: Option<Vec<usize>>,
+}
+
+impl<T: Clone> CartesianProduct<T> {
+    pub fn new(lists: Vec<Vec<T>>) -> CartesianProduct<T> {
+        let pos = if lists.iter().all(|l| !l.is_empty()) {
+            Some(vec![0; lists.len()])
+        } else {
+            None
+        };
+        CartesianProduct { lists, pos }
+    }
+}
+
+impl<T: Clone> Iterator for CartesianProduct<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        let lists = &self.lists;
+        let pos = self.pos.as_mut()?;
+        let product = pos.iter().zip(lists).map(|(&i, l)| l[i].clone()).collect();
+        // Turn the odometer: the last wheel that isn't at its end moves on,
+        // and every wheel after it goes back to the start.
+        match (0..pos.len()).rev().find(|&i| pos[i] + 1 < lists[i].len()) {
+            Some(i) => {
+                pos[i] += 1;
+                for p in &mut pos[(i + 1)..] {
+                    *p = 0;
+                }
+            }
+            None => self.pos = None,
+        }
+        Some(product)
+    }
+}
+
 #[cfg(test)]
 mod test {
     fn pts(n: usize) -> String {
@@ -68,4 +347,153 @@ mod test {
 
         assert_eq!(super::permutations(0..6).len(), 6 * 5 * 4 * 3 * 2);
     }
+
+    #[test]
+    fn next_in_place() {
+        use super::next_permutation;
+
+        let mut v = [1, 2, 3];
+        let mut seen = vec![v];
+        while next_permutation(&mut v) {
+            seen.push(v);
+        }
+        assert_eq!(
+            seen,
+            vec![
+                [1, 2, 3],
+                [1, 3, 2],
+                [2, 1, 3],
+                [2, 3, 1],
+                [3, 1, 2],
+                [3, 2, 1]
+            ]
+        );
+        assert_eq!(v, [1, 2, 3]);
+
+        let mut dups = ['a', 'a', 'b'];
+        let mut n = 1;
+        while next_permutation(&mut dups) {
+            n += 1;
+        }
+        assert_eq!(n, 3);
+
+        let mut empty: [u8; 0] = [];
+        assert!(!next_permutation(&mut empty));
+    }
+
+    #[test]
+    fn for_each_in_place() {
+        use super::{for_each_permutation, Permutations};
+
+        let mut v = [3, 1, 2];
+        let mut seen = Vec::new();
+        for_each_permutation(&mut v, |p| seen.push(p.to_vec()));
+        assert_eq!(seen, Permutations::new(1..=3).collect::<Vec<_>>());
+        assert_eq!(v, [1, 2, 3]);
+
+        let mut n = 0;
+        for_each_permutation(&mut ['b', 'a', 'b'], |_| n += 1);
+        assert_eq!(n, 3);
+    }
+
+    #[test]
+    fn lazy() {
+        use super::{permutations, permutations_len, Permutations};
+
+        assert_eq!(permutations_len(0), Some(1));
+        assert_eq!(permutations_len(5), Some(120));
+        assert_eq!(permutations_len(20), Some(2_432_902_008_176_640_000));
+        assert_eq!(permutations_len(21), None);
+
+        for n in 1..6 {
+            let mut lazy: Vec<Vec<usize>> = Permutations::new(0..n).collect();
+            assert_eq!(lazy.len(), permutations_len(n).unwrap());
+            assert!(lazy.windows(2).all(|w| w[0] < w[1]));
+            let mut eager = permutations(0..n);
+            eager.sort();
+            lazy.sort();
+            assert_eq!(lazy, eager);
+        }
+        assert_eq!(
+            Permutations::new(Vec::<u8>::new()).collect::<Vec<_>>(),
+            vec![Vec::<u8>::new()]
+        );
+
+        // Too many to build all at once, but the first few are quick.
+        let mut big = Permutations::new(0..20);
+        assert_eq!(big.nth(2).unwrap()[17..], [18, 17, 19]);
+    }
+
+    #[test]
+    fn combinations() {
+        use super::Combinations;
+
+        let c: Vec<String> = Combinations::new("abcd".chars(), 2)
+            .map(|v| v.into_iter().collect())
+            .collect();
+        assert_eq!(c, vec!["ab", "ac", "ad", "bc", "bd", "cd"]);
+        assert_eq!(Combinations::new(0..10, 3).count(), 120);
+        assert_eq!(Combinations::new(0..3, 0).collect::<Vec<_>>(), vec![vec![]]);
+        assert_eq!(Combinations::new(0..3, 3).count(), 1);
+        assert_eq!(Combinations::new(0..3, 4).count(), 0);
+    }
+
+    #[test]
+    fn subsets() {
+        use super::{gray_code_flips, Subsets};
+
+        let all: Vec<Vec<char>> = Subsets::new("abc".chars()).collect();
+        assert_eq!(all.len(), 8);
+        assert!(all[0].is_empty());
+        assert_eq!(all[1], vec!['a']);
+        assert_eq!(all[2], vec!['a', 'b']);
+        assert_eq!(all[3], vec!['b']);
+        for w in all.windows(2) {
+            assert_eq!((w[0].len() as isize - w[1].len() as isize).abs(), 1);
+        }
+        let mut sorted = all.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 8);
+
+        // Replaying the flips visits the same subsets.
+        let mut held = [false; 3];
+        for (flip, subset) in gray_code_flips(3).zip(&all[1..]) {
+            held[flip] = !held[flip];
+            let expected: Vec<char> = "abc"
+                .chars()
+                .zip(&held)
+                .filter(|(_, h)| **h)
+                .map(|(c, _)| c)
+                .collect();
+            assert_eq!(&expected, subset);
+        }
+        assert_eq!(Subsets::new(Vec::<u8>::new()).count(), 1);
+    }
+
+    #[test]
+    fn multiset_permutations() {
+        use super::MultisetPermutations;
+
+        let p: Vec<String> = MultisetPermutations::new("baa".chars())
+            .map(|v| v.into_iter().collect())
+            .collect();
+        assert_eq!(p, vec!["aab", "aba", "baa"]);
+        assert_eq!(MultisetPermutations::new(vec![1, 1, 2, 2]).count(), 6);
+        assert_eq!(MultisetPermutations::new(0..4).count(), 24);
+    }
+
+    #[test]
+    fn cartesian_product() {
+        use super::CartesianProduct;
+
+        let p: Vec<Vec<u8>> =
+            CartesianProduct::new(vec![vec![1, 2], vec![3], vec![4, 5]]).collect();
+        assert_eq!(
+            p,
+            vec![vec![1, 3, 4], vec![1, 3, 5], vec![2, 3, 4], vec![2, 3, 5]]
+        );
+        assert_eq!(CartesianProduct::<u8>::new(vec![]).count(), 1);
+        assert_eq!(CartesianProduct::new(vec![vec![1], vec![]]).count(), 0);
+    }
 }
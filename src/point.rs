@@ -13,7 +13,13 @@
 // limitations under the License.
 
 //! Simple 2D integer-indexed point.
+//!
+//! Points can also be used as vectors: they can be added, subtracted,
+//! negated, and multiplied by a scalar. Like stepping to a neighbor, these
+//! panic rather than wrapping if they overflow.
 use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
 
 #[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub struct Point {
@@ -52,4 +58,188 @@ impl Point {
     pub fn neighbors(&self) -> Vec<Point> {
         vec![self.left(), self.right(), self.up(), self.down()]
     }
+
+    /// The 8 neighbors, including diagonals, in row-major order.
+    pub fn neighbors8(&self) -> Vec<Point> {
+        let mut v = Vec::with_capacity(8);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if dx != 0 || dy != 0 {
+                    v.push(point(
+                        self.x.checked_add(dx).unwrap(),
+                        self.y.checked_add(dy).unwrap(),
+                    ));
+                }
+            }
+        }
+        v
+    }
+
+    /// Distance moving only horizontally and vertically.
+    pub fn manhattan(&self, other: Point) -> isize {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    /// Distance moving in any of 8 directions, so that diagonal steps count
+    /// as one.
+    pub fn chebyshev(&self, other: Point) -> isize {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePointError {
+    pub text: String,
+}
+
+impl fmt::Display for ParsePointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "can't parse point from {:?}", self.text)
+    }
+}
+
+impl std::error::Error for ParsePointError {}
+
+impl FromStr for Point {
+    type Err = ParsePointError;
+
+    /// Parse "x,y", with optional spaces around either number.
+    fn from_str(s: &str) -> Result<Point, ParsePointError> {
+        let err = || ParsePointError { text: s.to_owned() };
+        let mut parts = s.split(',');
+        let mut number = || -> Result<isize, ParsePointError> {
+            parts
+                .next()
+                .ok_or_else(err)?
+                .trim()
+                .parse()
+                .map_err(|_| err())
+        };
+        let p = point(number()?, number()?);
+        if parts.next().is_some() {
+            return Err(err());
+        }
+        Ok(p)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+    fn add(self, other: Point) -> Point {
+        point(
+            self.x.checked_add(other.x).unwrap(),
+            self.y.checked_add(other.y).unwrap(),
+        )
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, other: Point) {
+        *self = *self + other
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+    fn sub(self, other: Point) -> Point {
+        point(
+            self.x.checked_sub(other.x).unwrap(),
+            self.y.checked_sub(other.y).unwrap(),
+        )
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, other: Point) {
+        *self = *self - other
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+    fn neg(self) -> Point {
+        point(self.x.checked_neg().unwrap(), self.y.checked_neg().unwrap())
+    }
+}
+
+impl Mul<isize> for Point {
+    type Output = Point;
+    fn mul(self, k: isize) -> Point {
+        point(
+            self.x.checked_mul(k).unwrap(),
+            self.y.checked_mul(k).unwrap(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = point(1, 2);
+        let b = point(-3, 5);
+        assert_eq!(a + b, point(-2, 7));
+        assert_eq!(a - b, point(4, -3));
+        assert_eq!(-a, point(-1, -2));
+        assert_eq!(b * 3, point(-9, 15));
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_out_of_range() {
+        let _ = point(isize::MAX, 0) + point(1, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mul_out_of_range() {
+        let _ = point(0, isize::MIN) * 2;
+    }
+
+    #[test]
+    #[should_panic]
+    fn neighbors8_out_of_range() {
+        point(0, isize::MIN).neighbors8();
+    }
+
+    #[test]
+    fn distances() {
+        assert_eq!(point(1, 2).manhattan(point(-3, 5)), 7);
+        assert_eq!(point(1, 2).chebyshev(point(-3, 5)), 4);
+        assert_eq!(point(0, 0).manhattan(point(0, 0)), 0);
+    }
+
+    #[test]
+    fn neighbors8() {
+        let n = point(0, 0).neighbors8();
+        assert_eq!(n.len(), 8);
+        assert_eq!(n[0], point(-1, -1));
+        assert_eq!(n[7], point(1, 1));
+        assert!(n.iter().all(|p| p.chebyshev(point(0, 0)) == 1));
+    }
+
+    #[test]
+    fn display_and_parse() {
+        assert_eq!(point(-4, 17).to_string(), "-4,17");
+        assert_eq!("-4,17".parse(), Ok(point(-4, 17)));
+        assert_eq!(" 3 , 4 ".parse(), Ok(point(3, 4)));
+        assert!("3".parse::<Point>().is_err());
+        assert!("3,4,5".parse::<Point>().is_err());
+        assert_eq!(
+            "x,1".parse::<Point>().unwrap_err().to_string(),
+            "can't parse point from \"x,1\""
+        );
+    }
 }
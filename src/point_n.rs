@@ -0,0 +1,281 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integer points in any number of dimensions.
+//!
+//! Like `Point`, a `PointN` can also be used as a vector: they can be added,
+//! subtracted, negated, and multiplied by a scalar, component by component.
+//!
+//! Points are ordered by their last component first, so that a `PointN<2>`
+//! sorts like a `Point`, by y and then x, and a `Point3` sorts by z first.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+
+use crate::{point, Point};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PointN<const D: usize>(pub [isize; D]);
+
+/// A point in 3d space.
+pub type Point3 = PointN<3>;
+
+impl<const D: usize> PointN<D> {
+    pub fn new(c: [isize; D]) -> PointN<D> {
+        PointN(c)
+    }
+
+    /// The point with every component 0.
+    pub fn zero() -> PointN<D> {
+        PointN([0; D])
+    }
+
+    /// Apply `f` to every component.
+    pub fn map<F: Fn(isize) -> isize>(self, f: F) -> PointN<D> {
+        let mut c = self.0;
+        for v in c.iter_mut() {
+            *v = f(*v);
+        }
+        PointN(c)
+    }
+
+    /// Combine corresponding components of two points.
+    pub fn zip_with<F: Fn(isize, isize) -> isize>(self, other: PointN<D>, f: F) -> PointN<D> {
+        let mut c = self.0;
+        for (v, o) in c.iter_mut().zip(other.0.iter()) {
+            *v = f(*v, *o);
+        }
+        PointN(c)
+    }
+
+    /// The sign of each component: -1, 0 or 1.
+    pub fn signum(self) -> PointN<D> {
+        self.map(isize::signum)
+    }
+
+    /// The sum of the absolute values of the components, which is the
+    /// "energy" on day 12.
+    pub fn abs_sum(&self) -> isize {
+        self.0.iter().map(|v| v.abs()).sum()
+    }
+
+    /// Distance moving only along the axes.
+    pub fn manhattan(&self, other: PointN<D>) -> isize {
+        (*self - other).abs_sum()
+    }
+
+    /// The `2 * D` neighbors one step along each axis, in order of axis,
+    /// with the lower neighbor first.
+    pub fn neighbors(&self) -> Vec<PointN<D>> {
+        let mut v = Vec::with_capacity(2 * D);
+        for axis in 0..D {
+            for &delta in &[-1, 1] {
+                let mut n = *self;
+                n.0[axis] += delta;
+                v.push(n);
+            }
+        }
+        v
+    }
+
+    /// The `3^D - 1` neighbors including diagonals, in increasing order.
+    pub fn neighbors_all(&self) -> Vec<PointN<D>> {
+        let mut v = vec![*self - PointN([1; D])];
+        for axis in (0..D).rev() {
+            v = v
+                .into_iter()
+                .flat_map(|p| {
+                    (0..3).map(move |i| {
+                        let mut n = p;
+                        n.0[axis] += i;
+                        n
+                    })
+                })
+                .collect();
+        }
+        v.sort();
+        v.retain(|n| n != self);
+        v
+    }
+}
+
+impl<const D: usize> Ord for PointN<D> {
+    fn cmp(&self, other: &PointN<D>) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl<const D: usize> PartialOrd for PointN<D> {
+    fn partial_cmp(&self, other: &PointN<D>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Point3 {
+    /// Extend a 2d point with a third component.
+    pub fn from_point(p: Point, z: isize) -> Point3 {
+        PointN([p.x, p.y, z])
+    }
+
+    /// The first two components, as a 2d point.
+    pub fn xy(&self) -> Point {
+        point(self.0[0], self.0[1])
+    }
+
+    pub fn z(&self) -> isize {
+        self.0[2]
+    }
+}
+
+impl From<Point> for PointN<2> {
+    fn from(p: Point) -> PointN<2> {
+        PointN([p.x, p.y])
+    }
+}
+
+impl From<PointN<2>> for Point {
+    fn from(p: PointN<2>) -> Point {
+        point(p.0[0], p.0[1])
+    }
+}
+
+impl<const D: usize> fmt::Display for PointN<D> {
+    /// Components separated by commas, like `Point`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, v) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", v)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const D: usize> Index<usize> for PointN<D> {
+    type Output = isize;
+    fn index(&self, axis: usize) -> &isize {
+        &self.0[axis]
+    }
+}
+
+impl<const D: usize> IndexMut<usize> for PointN<D> {
+    fn index_mut(&mut self, axis: usize) -> &mut isize {
+        &mut self.0[axis]
+    }
+}
+
+impl<const D: usize> Add for PointN<D> {
+    type Output = PointN<D>;
+    fn add(self, other: PointN<D>) -> PointN<D> {
+        self.zip_with(other, |a, b| a + b)
+    }
+}
+
+impl<const D: usize> AddAssign for PointN<D> {
+    fn add_assign(&mut self, other: PointN<D>) {
+        *self = *self + other
+    }
+}
+
+impl<const D: usize> Sub for PointN<D> {
+    type Output = PointN<D>;
+    fn sub(self, other: PointN<D>) -> PointN<D> {
+        self.zip_with(other, |a, b| a - b)
+    }
+}
+
+impl<const D: usize> SubAssign for PointN<D> {
+    fn sub_assign(&mut self, other: PointN<D>) {
+        *self = *self - other
+    }
+}
+
+impl<const D: usize> Neg for PointN<D> {
+    type Output = PointN<D>;
+    fn neg(self) -> PointN<D> {
+        self.map(|v| -v)
+    }
+}
+
+impl<const D: usize> Mul<isize> for PointN<D> {
+    type Output = PointN<D>;
+    fn mul(self, k: isize) -> PointN<D> {
+        self.map(|v| v * k)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn arithmetic() {
+        let a = PointN([1, -2, 3]);
+        let b = PointN([4, 5, -6]);
+        assert_eq!(a + b, PointN([5, 3, -3]));
+        assert_eq!(a - b, PointN([-3, -7, 9]));
+        assert_eq!(-a, PointN([-1, 2, -3]));
+        assert_eq!(a * 2, PointN([2, -4, 6]));
+        assert_eq!((b - a).signum(), PointN([1, 1, -1]));
+        let mut c = Point3::zero();
+        c += b;
+        c -= a;
+        c[0] += 10;
+        assert_eq!(c, PointN([13, 7, -9]));
+        assert_eq!(a.abs_sum(), 6);
+        assert_eq!(a.manhattan(b), 19);
+        assert_eq!(a.to_string(), "1,-2,3");
+    }
+
+    #[test]
+    fn neighbors() {
+        let p = PointN([0, 0, 0, 0]);
+        let n = p.neighbors();
+        assert_eq!(n.len(), 8);
+        assert_eq!(n[0], PointN([-1, 0, 0, 0]));
+        assert!(n.iter().all(|q| q.manhattan(p) == 1));
+
+        let all = p.neighbors_all();
+        assert_eq!(all.len(), 80);
+        assert_eq!(all[0], PointN([-1, -1, -1, -1]));
+        assert_eq!(all[79], PointN([1, 1, 1, 1]));
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 80);
+        assert!(!all.contains(&p));
+    }
+
+    #[test]
+    fn conversions() {
+        let p = Point3::from_point(point(3, 4), -1);
+        assert_eq!(p.xy(), point(3, 4));
+        assert_eq!(p.z(), -1);
+        let q: PointN<2> = point(5, 6).into();
+        assert_eq!(Point::from(q), point(5, 6));
+    }
+
+    #[test]
+    fn ordering() {
+        let mut points = vec![point(2, 0), point(0, 1), point(1, 0), point(-1, 1)];
+        let mut as_n: Vec<PointN<2>> = points.iter().map(|&p| p.into()).collect();
+        points.sort();
+        as_n.sort();
+        assert_eq!(
+            as_n.into_iter().map(Point::from).collect::<Vec<_>>(),
+            points
+        );
+        assert!(PointN([9, 9, 0]) < PointN([0, 0, 1]));
+    }
+}
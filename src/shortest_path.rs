@@ -12,61 +12,1123 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Find the shortest path in a graph, using Djikstra's method.
+//! Find the shortest path in a graph, using Djikstra's method, or A* when
+//! there's a good estimate of the remaining distance.
+//!
+//! Distances can be any type that implements `Cost`: integers, `F64Cost`
+//! for floating point, or tuples of costs, which are compared
+//! lexicographically so that the later parts break ties.
 
 use core::hash::Hash;
-use std::collections::{HashMap, BinaryHeap};
+use std::cmp::{Ordering, Reverse};
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
 
-type D = isize;
+use crate::Point;
+
+/// A distance, or the cost of a step.
+pub trait Cost: Copy + Ord {
+    fn zero() -> Self;
+
+    /// Add two costs. This should never be less than either of them.
+    fn plus(self, other: Self) -> Self;
+}
+
+macro_rules! integer_cost {
+    ($($t:ty),*) => {
+        $(
+            impl Cost for $t {
+                fn zero() -> $t {
+                    0
+                }
+
+                fn plus(self, other: $t) -> $t {
+                    self + other
+                }
+            }
+        )*
+    };
+}
+
+integer_cost!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl<A: Cost, B: Cost> Cost for (A, B) {
+    fn zero() -> (A, B) {
+        (A::zero(), B::zero())
+    }
+
+    fn plus(self, other: (A, B)) -> (A, B) {
+        (self.0.plus(other.0), self.1.plus(other.1))
+    }
+}
+
+impl<A: Cost, B: Cost, C: Cost> Cost for (A, B, C) {
+    fn zero() -> (A, B, C) {
+        (A::zero(), B::zero(), C::zero())
+    }
+
+    fn plus(self, other: (A, B, C)) -> (A, B, C) {
+        (
+            self.0.plus(other.0),
+            self.1.plus(other.1),
+            self.2.plus(other.2),
+        )
+    }
+}
+
+/// A floating-point cost, ordered by `f64::total_cmp`.
+///
+/// Costs shouldn't be NaN or negative.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct F64Cost(pub f64);
+
+impl PartialEq for F64Cost {
+    fn eq(&self, other: &F64Cost) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for F64Cost {}
+
+impl PartialOrd for F64Cost {
+    fn partial_cmp(&self, other: &F64Cost) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for F64Cost {
+    fn cmp(&self, other: &F64Cost) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Cost for F64Cost {
+    fn zero() -> F64Cost {
+        F64Cost(0.0)
+    }
+
+    fn plus(self, other: F64Cost) -> F64Cost {
+        F64Cost(self.0 + other.0)
+    }
+}
 
 /// Find the shortest path in a graph, using Djikstra's method.
 ///
 /// Positions are identified by type `P` which might be a `Point` or something
 /// more complicated to describe additional state. Distances are measured
-/// as isizes.
+/// in any `Cost` type `C`, such as isize.
 ///
 /// This takes a callback which returns all the neighbors from `p: P` and
 /// the incremental distance to them, as tuples. The neighbor callback is mut to allow
 /// for internal caching.
-pub fn shortest_distance<P, N>(origin: P, dest: P, nbr_fn: &mut N) -> D
+///
+/// Returns None if the destination can't be reached.
+pub fn shortest_distance<P, C, N>(origin: P, dest: P, nbr_fn: &mut N) -> Option<C>
 where
     P: Eq + Ord + Copy + Hash,
-    N: FnMut(P) -> Vec<(P, D)>,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
 {
     shortest_distance_fn(origin, |&p| dest == p, nbr_fn)
 }
 
 /// Calculate the shortest distance, with a callback that says whether a point is the destination.
-pub fn shortest_distance_fn<P, N, DF>(origin: P, dest_fn: DF, nbr_fn: &mut N) -> D
+pub fn shortest_distance_fn<P, C, N, DF>(origin: P, dest_fn: DF, nbr_fn: &mut N) -> Option<C>
+where
+    P: Eq + Ord + Copy + Hash,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+    DF: Fn(&P) -> bool,
+{
+    shortest_path_fn(origin, dest_fn, nbr_fn).map(|path| path.dist)
+}
+
+/// A route through the graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<P, C = isize> {
+    /// Total distance.
+    pub dist: C,
+    /// Every position from the origin to the destination, inclusive, with
+    /// the cost of the step into it. The origin's cost is zero.
+    pub steps: Vec<(P, C)>,
+}
+
+impl<P: Copy, C> Path<P, C> {
+    /// The positions along the path, without the costs.
+    pub fn positions(&self) -> Vec<P> {
+        self.steps.iter().map(|(p, _)| *p).collect()
+    }
+
+    /// The last position.
+    pub fn dest(&self) -> P {
+        self.steps.last().unwrap().0
+    }
+}
+
+/// Find the shortest path from `origin` to `dest`, and return the whole
+/// route.
+pub fn shortest_path<P, C, N>(origin: P, dest: P, nbr_fn: &mut N) -> Option<Path<P, C>>
+where
+    P: Eq + Ord + Copy + Hash,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+{
+    shortest_path_fn(origin, |&p| dest == p, nbr_fn)
+}
+
+/// Find the shortest path to any point for which `dest_fn` is true, and
+/// return the whole route.
+pub fn shortest_path_fn<P, C, N, DF>(origin: P, dest_fn: DF, nbr_fn: &mut N) -> Option<Path<P, C>>
+where
+    P: Eq + Ord + Copy + Hash,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+    DF: Fn(&P) -> bool,
+{
+    astar_path_fn(origin, dest_fn, nbr_fn, |_| C::zero())
+}
+
+/// Find the shortest distance using A*.
+///
+/// See `astar_path_fn`.
+pub fn astar_distance<P, C, N, H>(origin: P, dest: P, nbr_fn: &mut N, heuristic: H) -> Option<C>
+where
+    P: Eq + Ord + Copy + Hash,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+    H: Fn(&P) -> C,
+{
+    astar_path_fn(origin, |&p| dest == p, nbr_fn, heuristic).map(|path| path.dist)
+}
+
+/// Find the shortest path using A*, which looks first at points that the
+/// heuristic says are closest to the destination.
+///
+/// The heuristic estimates the remaining distance from a point to the
+/// nearest destination. It must be admissible: it must never be more than
+/// the true distance, or the path found might not be the shortest. A
+/// heuristic that's always zero gives Dijkstra's method.
+pub fn astar_path_fn<P, C, N, DF, H>(
+    origin: P,
+    dest_fn: DF,
+    nbr_fn: &mut N,
+    heuristic: H,
+) -> Option<Path<P, C>>
+where
+    P: Eq + Ord + Copy + Hash,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+    DF: Fn(&P) -> bool,
+    H: Fn(&P) -> C,
+{
+    bounded_search(origin, dest_fn, nbr_fn, heuristic, Limits::default()).path
+}
+
+/// Limits on how far a search goes before giving up.
+///
+/// These are needed when the graph is infinite and the destination might
+/// never be found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits<C = isize> {
+    /// Don't look for paths longer than this.
+    pub max_dist: Option<C>,
+    /// Give up after expanding this many points.
+    pub max_expanded: Option<usize>,
+}
+
+impl<C> Default for Limits<C> {
+    /// No limits.
+    fn default() -> Limits<C> {
+        Limits {
+            max_dist: None,
+            max_expanded: None,
+        }
+    }
+}
+
+/// How much work a search did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of points whose neighbors were fetched.
+    pub expanded: usize,
+    /// The most points that were ever waiting in the queue.
+    pub queue_peak: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult<P, C = isize> {
+    /// The shortest path, or None if no destination was found within the
+    /// limits.
+    pub path: Option<Path<P, C>>,
+    pub stats: Stats,
+}
+
+/// Find the shortest path using A*, stopping at the given limits, and
+/// report how much work it took.
+///
+/// See `astar_path_fn` for the requirements on the heuristic.
+pub fn bounded_search<P, C, N, DF, H>(
+    origin: P,
+    dest_fn: DF,
+    nbr_fn: &mut N,
+    heuristic: H,
+    limits: Limits<C>,
+) -> SearchResult<P, C>
 where
     P: Eq + Ord + Copy + Hash,
-    N: FnMut(P) -> Vec<(P, D)>,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
     DF: Fn(&P) -> bool,
+    H: Fn(&P) -> C,
 {
-    // Next points to visit, indexed by the *negative* distance, so that the
-    // greatest value is the shortest.
-    let mut queue = BinaryHeap::<(D, P)>::new();
+    // A point can be skipped if even the heuristic says it's too far.
+    let too_far = |d: C, p: &P| {
+        limits
+            .max_dist
+            .is_some_and(|max| d.plus(heuristic(p)) > max)
+    };
+    let mut stats = Stats::default();
+    // Next points to visit, shortest estimated total distance first, and
+    // then longest distance so far, to prefer points nearer the destination.
+    let mut queue = BinaryHeap::<(Reverse<C>, C, P)>::new();
     // Shortest known distance to reach any point.
-    let mut best = HashMap::<P, D>::new();
-    queue.push((0, origin));
-    loop {
-        let (d, p) = queue
-            .pop()
-            .expect("heap is empty without reaching destination");
-        let d = -d;
+    let mut best = HashMap::<P, C>::new();
+    // The point each point was best reached from, and the cost of that step.
+    let mut prev = HashMap::<P, (P, C)>::new();
+    best.insert(origin, C::zero());
+    if !too_far(C::zero(), &origin) {
+        queue.push((Reverse(heuristic(&origin)), C::zero(), origin));
+    }
+    stats.queue_peak = queue.len();
+    while let Some((_, d, p)) = queue.pop() {
         if dest_fn(&p) {
-            // Found a shortest path to the end
-            return d;
+            // Found a shortest path to the end; walk back to the start.
+            let mut steps = Vec::new();
+            let mut q = p;
+            while let Some(&(from, step)) = prev.get(&q) {
+                steps.push((q, step));
+                q = from;
+            }
+            steps.push((origin, C::zero()));
+            steps.reverse();
+            return SearchResult {
+                path: Some(Path { dist: d, steps }),
+                stats,
+            };
         }
+        if best[&p] < d {
+            continue; // Already expanded by a shorter route.
+        }
+        if limits.max_expanded.is_some_and(|max| stats.expanded >= max) {
+            break;
+        }
+        stats.expanded += 1;
         for (np, step) in nbr_fn(p) {
-            let nd = step + d;
+            let nd = d.plus(step);
             if let Some(prev_d) = best.get(&np) {
                 if nd >= *prev_d {
                     continue; // Already found a shorter path; don't revisit.
                 }
             }
+            if too_far(nd, &np) {
+                continue;
+            }
             best.insert(np, nd);
-            queue.push((-nd, np));
+            prev.insert(np, (p, step));
+            queue.push((Reverse(nd.plus(heuristic(&np))), nd, np));
+        }
+        stats.queue_peak = stats.queue_peak.max(queue.len());
+    }
+    SearchResult { path: None, stats }
+}
+
+/// A heuristic for searches over a grid moving in 4 directions with a cost
+/// of 1 per step: the Manhattan distance to `dest`.
+pub fn manhattan_to(dest: Point) -> impl Fn(&Point) -> isize {
+    move |p| p.manhattan(dest)
+}
+
+/// Shortest distance from `origin` to every point that can be reached,
+/// including `origin` itself, using Dijkstra's method.
+pub fn distances_from<P, C, N>(origin: P, nbr_fn: &mut N) -> HashMap<P, C>
+where
+    P: Eq + Ord + Copy + Hash,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+{
+    let mut queue = BinaryHeap::<(Reverse<C>, P)>::new();
+    let mut best = HashMap::<P, C>::new();
+    best.insert(origin, C::zero());
+    queue.push((Reverse(C::zero()), origin));
+    while let Some((Reverse(d), p)) = queue.pop() {
+        if best[&p] < d {
+            continue; // Superseded by a shorter route.
+        }
+        for (np, step) in nbr_fn(p) {
+            let nd = d.plus(step);
+            if best.get(&np).is_none_or(|&prev_d| nd < prev_d) {
+                best.insert(np, nd);
+                queue.push((Reverse(nd), np));
+            }
+        }
+    }
+    best
+}
+
+/// Find the shortest distance by searching outwards from both ends at
+/// once, and stopping when the searches meet.
+///
+/// This usually looks at far fewer points than searching from one end,
+/// when the graph branches a lot. The graph must be undirected: every
+/// neighbor of `p` must have `p` as a neighbor, with the same cost.
+pub fn bidirectional_distance<P, C, N>(origin: P, dest: P, nbr_fn: &mut N) -> Option<C>
+where
+    P: Eq + Ord + Copy + Hash,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+{
+    // Forwards from the origin, and backwards from the destination.
+    let mut best = [HashMap::<P, C>::new(), HashMap::<P, C>::new()];
+    let mut queues = [
+        BinaryHeap::<(Reverse<C>, P)>::new(),
+        BinaryHeap::<(Reverse<C>, P)>::new(),
+    ];
+    for (side, &p) in [origin, dest].iter().enumerate() {
+        best[side].insert(p, C::zero());
+        queues[side].push((Reverse(C::zero()), p));
+    }
+    // The shortest route found so far through a point seen from both ends.
+    let mut shortest = if origin == dest {
+        Some(C::zero())
+    } else {
+        None
+    };
+    loop {
+        let mut tops = [None, None];
+        for side in 0..2 {
+            // Drop entries superseded by a shorter route.
+            while let Some(&(Reverse(d), p)) = queues[side].peek() {
+                if best[side][&p] < d {
+                    queues[side].pop();
+                } else {
+                    tops[side] = Some(d);
+                    break;
+                }
+            }
+        }
+        let side = match tops {
+            [Some(a), Some(b)] => {
+                // Nothing still queued can lead to a shorter route.
+                if shortest.is_some_and(|s| a.plus(b) >= s) {
+                    return shortest;
+                }
+                if a <= b {
+                    0
+                } else {
+                    1
+                }
+            }
+            // One side has seen everything it can reach, so every route
+            // has already been found.
+            _ => return shortest,
+        };
+        let (Reverse(d), p) = queues[side].pop().unwrap();
+        for (np, step) in nbr_fn(p) {
+            let nd = d.plus(step);
+            if best[side].get(&np).is_none_or(|&prev_d| nd < prev_d) {
+                best[side].insert(np, nd);
+                queues[side].push((Reverse(nd), np));
+            }
+            if let Some(&other) = best[1 - side].get(&np) {
+                let total = nd.plus(other);
+                if shortest.is_none_or(|s| total < s) {
+                    shortest = Some(total);
+                }
+            }
+        }
+    }
+}
+
+/// Find the shortest path using IDA*: repeated depth-first searches that
+/// each go a little further, as judged by the heuristic.
+///
+/// This needs memory only for the current path, rather than for every
+/// point seen, but it may look at the same points many times over. It
+/// suits graphs that are too big to remember, with a good heuristic. See
+/// `astar_path_fn` for the requirements on the heuristic.
+///
+/// If no destination can be reached this returns None once every point has
+/// been seen, which never happens on an infinite graph.
+pub fn ida_star<P, C, N, DF, H>(
+    origin: P,
+    dest_fn: DF,
+    nbr_fn: &mut N,
+    heuristic: H,
+) -> Option<Path<P, C>>
+where
+    P: Eq + Copy,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+    DF: Fn(&P) -> bool,
+    H: Fn(&P) -> C,
+{
+    let mut path = vec![(origin, C::zero())];
+    let mut threshold = heuristic(&origin);
+    loop {
+        match ida_probe(
+            &mut path,
+            C::zero(),
+            threshold,
+            &dest_fn,
+            nbr_fn,
+            &heuristic,
+        ) {
+            Probe::Found(dist) => return Some(Path { dist, steps: path }),
+            Probe::Over(Some(next)) => threshold = next,
+            Probe::Over(None) => return None,
+        }
+    }
+}
+
+enum Probe<C> {
+    /// Reached a destination at this distance; the path is left in place.
+    Found(C),
+    /// Didn't find a destination within the threshold. Contains the
+    /// smallest estimate that was over the threshold, if any.
+    Over(Option<C>),
+}
+
+/// Search depth-first from the end of `path`, which is `g` from the
+/// origin, going no further than `threshold`.
+fn ida_probe<P, C, N, DF, H>(
+    path: &mut Vec<(P, C)>,
+    g: C,
+    threshold: C,
+    dest_fn: &DF,
+    nbr_fn: &mut N,
+    heuristic: &H,
+) -> Probe<C>
+where
+    P: Eq + Copy,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+    DF: Fn(&P) -> bool,
+    H: Fn(&P) -> C,
+{
+    let p = path[path.len() - 1].0;
+    let estimate = g.plus(heuristic(&p));
+    if estimate > threshold {
+        return Probe::Over(Some(estimate));
+    }
+    if dest_fn(&p) {
+        return Probe::Found(g);
+    }
+    let mut next: Option<C> = None;
+    for (np, step) in nbr_fn(p) {
+        if path.iter().any(|(q, _)| *q == np) {
+            continue; // Don't go round in circles.
+        }
+        path.push((np, step));
+        match ida_probe(path, g.plus(step), threshold, dest_fn, nbr_fn, heuristic) {
+            Probe::Found(dist) => return Probe::Found(dist),
+            Probe::Over(Some(over)) => {
+                if next.is_none_or(|n| over < n) {
+                    next = Some(over);
+                }
+            }
+            Probe::Over(None) => (),
+        }
+        path.pop();
+    }
+    Probe::Over(next)
+}
+
+/// Shortest distances between every pair of a set of nodes, each
+/// identified by a label.
+#[derive(Debug, Clone)]
+pub struct DistanceTable<L, C = isize> {
+    labels: Vec<L>,
+    index: HashMap<L, usize>,
+    /// Row-major, from row to column, with None where there's no route.
+    dist: Vec<Option<C>>,
+}
+
+impl<L, C> DistanceTable<L, C>
+where
+    L: Eq + Copy + Hash,
+    C: Cost,
+{
+    fn empty(labels: Vec<L>) -> DistanceTable<L, C> {
+        let index = labels.iter().enumerate().map(|(i, l)| (*l, i)).collect();
+        let n = labels.len();
+        DistanceTable {
+            labels,
+            index,
+            dist: vec![None; n * n],
+        }
+    }
+
+    /// Find the distances between labelled points in a larger graph, by
+    /// searching from each of them.
+    ///
+    /// The routes can pass through any points, not just the labelled ones.
+    pub fn from_points<P, N>(nodes: &[(L, P)], nbr_fn: &mut N) -> DistanceTable<L, C>
+    where
+        P: Eq + Ord + Copy + Hash,
+        N: FnMut(P) -> Vec<(P, C)>,
+    {
+        let mut table = DistanceTable::empty(nodes.iter().map(|(l, _)| *l).collect());
+        let n = nodes.len();
+        for (i, (_, from)) in nodes.iter().enumerate() {
+            let dist = distances_from(*from, nbr_fn);
+            for (j, (_, to)) in nodes.iter().enumerate() {
+                table.dist[i * n + j] = dist.get(to).copied();
+            }
+        }
+        table
+    }
+
+    /// Find the distances between every pair of nodes, given the edges
+    /// leaving each node, using the Floyd-Warshall algorithm.
+    ///
+    /// Edges to nodes not in `labels` are ignored. This is quicker than
+    /// `from_points` when the graph is small and dense.
+    pub fn floyd_warshall<N>(labels: Vec<L>, edges: &mut N) -> DistanceTable<L, C>
+    where
+        N: FnMut(L) -> Vec<(L, C)>,
+    {
+        let mut table = DistanceTable::empty(labels);
+        let n = table.labels.len();
+        for i in 0..n {
+            table.dist[i * n + i] = Some(C::zero());
+            for (to, cost) in edges(table.labels[i]) {
+                if let Some(&j) = table.index.get(&to) {
+                    let d = &mut table.dist[i * n + j];
+                    if d.is_none_or(|old| cost < old) {
+                        *d = Some(cost);
+                    }
+                }
+            }
+        }
+        for k in 0..n {
+            for i in 0..n {
+                let ik = match table.dist[i * n + k] {
+                    Some(ik) => ik,
+                    None => continue,
+                };
+                for j in 0..n {
+                    if let Some(kj) = table.dist[k * n + j] {
+                        let through = ik.plus(kj);
+                        let d = &mut table.dist[i * n + j];
+                        if d.is_none_or(|old| through < old) {
+                            *d = Some(through);
+                        }
+                    }
+                }
+            }
+        }
+        table
+    }
+
+    pub fn labels(&self) -> &[L] {
+        &self.labels
+    }
+
+    /// The shortest distance from one node to another, or None if there's
+    /// no route or either isn't in the table.
+    pub fn get(&self, from: L, to: L) -> Option<C> {
+        let i = *self.index.get(&from)?;
+        let j = *self.index.get(&to)?;
+        self.dist[i * self.labels.len() + j]
+    }
+
+    /// Every node that can be reached from `from`, and its distance.
+    pub fn row(&self, from: L) -> impl Iterator<Item = (L, C)> + '_ {
+        let n = self.labels.len();
+        let start = self.index.get(&from).map_or(0, |i| i * n);
+        let len = if self.index.contains_key(&from) { n } else { 0 };
+        self.labels
+            .iter()
+            .zip(&self.dist[start..start + len])
+            .filter_map(|(l, d)| d.map(|d| (*l, d)))
+    }
+}
+
+/// Shortest distances from any point to every point reachable from it,
+/// searched the first time they're asked for and then remembered.
+pub struct DistanceCache<P, C, N> {
+    nbr_fn: N,
+    rows: HashMap<P, HashMap<P, C>>,
+}
+
+impl<P, C, N> DistanceCache<P, C, N>
+where
+    P: Eq + Ord + Copy + Hash,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+{
+    pub fn new(nbr_fn: N) -> DistanceCache<P, C, N> {
+        DistanceCache {
+            nbr_fn,
+            rows: HashMap::new(),
+        }
+    }
+
+    /// Distances from `from` to every point that can be reached.
+    pub fn distances_from(&mut self, from: P) -> &HashMap<P, C> {
+        let nbr_fn = &mut self.nbr_fn;
+        self.rows
+            .entry(from)
+            .or_insert_with(|| distances_from(from, nbr_fn))
+    }
+
+    /// The shortest distance from one point to another, or None if there's
+    /// no route.
+    pub fn get(&mut self, from: P, to: P) -> Option<C> {
+        self.distances_from(from).get(&to).copied()
+    }
+
+    /// Number of points whose distances have been searched.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// Number of steps from the nearest of `origins` to every point that can
+/// be reached, searching breadth-first in a graph where every step costs 1.
+///
+/// The neighbor callback returns the points one step from `p`.
+pub fn bfs_distances<P, I, N>(origins: I, nbr_fn: &mut N) -> HashMap<P, usize>
+where
+    P: Eq + Copy + Hash,
+    I: IntoIterator<Item = P>,
+    N: FnMut(P) -> Vec<P>,
+{
+    let mut dist = HashMap::new();
+    let mut queue = VecDeque::new();
+    for origin in origins {
+        if dist.insert(origin, 0).is_none() {
+            queue.push_back(origin);
+        }
+    }
+    while let Some(p) = queue.pop_front() {
+        let d = dist[&p];
+        for np in nbr_fn(p) {
+            if let Entry::Vacant(e) = dist.entry(np) {
+                e.insert(d + 1);
+                queue.push_back(np);
+            }
+        }
+    }
+    dist
+}
+
+/// Find the shortest path from `origin` to `dest`, searching
+/// breadth-first in a graph where every step costs 1.
+pub fn bfs_path<P, N>(origin: P, dest: P, nbr_fn: &mut N) -> Option<Path<P, usize>>
+where
+    P: Eq + Copy + Hash,
+    N: FnMut(P) -> Vec<P>,
+{
+    bfs_path_fn(Some(origin), |&p| dest == p, nbr_fn)
+}
+
+/// Find the shortest path from any of `origins` to any point for which
+/// `dest_fn` is true, searching breadth-first in a graph where every step
+/// costs 1.
+pub fn bfs_path_fn<P, I, N, DF>(origins: I, dest_fn: DF, nbr_fn: &mut N) -> Option<Path<P, usize>>
+where
+    P: Eq + Copy + Hash,
+    I: IntoIterator<Item = P>,
+    N: FnMut(P) -> Vec<P>,
+    DF: Fn(&P) -> bool,
+{
+    // The point each point was first reached from, or None for origins.
+    let mut prev = HashMap::<P, Option<P>>::new();
+    let mut queue = VecDeque::new();
+    for origin in origins {
+        if prev.insert(origin, None).is_none() {
+            queue.push_back(origin);
+        }
+    }
+    while let Some(p) = queue.pop_front() {
+        if dest_fn(&p) {
+            let mut positions = vec![p];
+            while let Some(from) = prev[positions.last().unwrap()] {
+                positions.push(from);
+            }
+            positions.reverse();
+            let steps: Vec<(P, usize)> = positions
+                .into_iter()
+                .enumerate()
+                .map(|(i, q)| (q, if i == 0 { 0 } else { 1 }))
+                .collect();
+            return Some(Path {
+                dist: steps.len() - 1,
+                steps,
+            });
+        }
+        for np in nbr_fn(p) {
+            if let Entry::Vacant(e) = prev.entry(np) {
+                e.insert(Some(p));
+                queue.push_back(np);
+            }
+        }
+    }
+    None
+}
+
+/// Find every shortest path to any point for which `dest_fn` is true.
+///
+/// If several destinations are equally close, paths to all of them are
+/// returned. The paths are sorted, and the result is empty if no
+/// destination can be reached.
+///
+/// Step costs must be more than zero, otherwise there might be infinitely
+/// many paths.
+pub fn all_shortest_paths_fn<P, C, N, DF>(origin: P, dest_fn: DF, nbr_fn: &mut N) -> Vec<Path<P, C>>
+where
+    P: Eq + Ord + Copy + Hash,
+    C: Cost,
+    N: FnMut(P) -> Vec<(P, C)>,
+    DF: Fn(&P) -> bool,
+{
+    let mut queue = BinaryHeap::<(Reverse<C>, P)>::new();
+    let mut best = HashMap::<P, C>::new();
+    // Every point each point was best reached from, and the step costs.
+    let mut prev = HashMap::<P, Vec<(P, C)>>::new();
+    let mut found: Option<C> = None;
+    let mut dests = Vec::new();
+    best.insert(origin, C::zero());
+    queue.push((Reverse(C::zero()), origin));
+    while let Some((Reverse(d), p)) = queue.pop() {
+        if best[&p] < d {
+            continue; // Superseded by a shorter route.
+        }
+        if found.is_some_and(|f| d > f) {
+            break;
+        }
+        if dest_fn(&p) {
+            found = Some(d);
+            dests.push(p);
+            continue;
+        }
+        for (np, step) in nbr_fn(p) {
+            let nd = d.plus(step);
+            match best.get(&np) {
+                Some(&prev_d) if nd > prev_d => continue,
+                Some(&prev_d) if nd == prev_d => prev.entry(np).or_default().push((p, step)),
+                _ => {
+                    best.insert(np, nd);
+                    prev.insert(np, vec![(p, step)]);
+                    queue.push((Reverse(nd), np));
+                }
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+    for dest in dests {
+        let mut tails = vec![vec![(dest, C::zero())]];
+        // Extend every partial path, built backwards from the destination,
+        // by each way of reaching its first point.
+        while let Some(mut tail) = tails.pop() {
+            let (first, _) = tail[tail.len() - 1];
+            match prev.get(&first) {
+                Some(froms) if first != origin => {
+                    for &(from, step) in froms {
+                        let mut t = tail.clone();
+                        let last = t.len() - 1;
+                        t[last].1 = step;
+                        t.push((from, C::zero()));
+                        tails.push(t);
+                    }
+                }
+                _ => {
+                    tail.reverse();
+                    paths.push(Path {
+                        dist: found.unwrap(),
+                        steps: tail,
+                    });
+                }
+            }
+        }
+    }
+    paths.sort_by(|a, b| a.steps.cmp(&b.steps));
+    paths
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A small undirected graph with two equally short ways from 0 to 3:
+    /// through 1 or through 2. 4 hangs off 3, and off 2 by a long edge.
+    fn diamond(p: u8) -> Vec<(u8, isize)> {
+        match p {
+            0 => vec![(1, 1), (2, 2)],
+            1 => vec![(0, 1), (3, 2)],
+            2 => vec![(0, 2), (3, 1), (4, 5)],
+            3 => vec![(1, 2), (2, 1), (4, 1)],
+            4 => vec![(2, 5), (3, 1)],
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn path() {
+        let path = shortest_path(0, 4, &mut diamond).unwrap();
+        assert_eq!(path.dist, 4);
+        assert_eq!(path.steps[0], (0, 0));
+        assert_eq!(path.dest(), 4);
+        assert_eq!(path.steps.iter().map(|(_, c)| c).sum::<isize>(), 4);
+        assert_eq!(shortest_distance(0, 4, &mut diamond), Some(4));
+        assert_eq!(shortest_distance(0, 9, &mut diamond), None);
+        assert_eq!(
+            shortest_path(2, 2, &mut diamond),
+            Some(Path {
+                dist: 0,
+                steps: vec![(2, 0)]
+            })
+        );
+    }
+
+    #[test]
+    fn all_paths() {
+        let paths = all_shortest_paths_fn(0, |&p| p == 4, &mut diamond);
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].steps, vec![(0, 0), (1, 1), (3, 2), (4, 1)]);
+        assert_eq!(paths[1].steps, vec![(0, 0), (2, 2), (3, 1), (4, 1)]);
+        assert!(paths.iter().all(|p| p.dist == 4));
+
+        let either = all_shortest_paths_fn(0, |&p| p == 1 || p == 2, &mut diamond);
+        assert_eq!(either.len(), 1);
+        assert_eq!(either[0].positions(), vec![0, 1]);
+
+        assert!(all_shortest_paths_fn(0, |&p| p == 9, &mut diamond).is_empty());
+    }
+
+    #[test]
+    fn astar_on_grid() {
+        use crate::{point, Matrix};
+
+        // A wall across most of the grid.
+        let m = Matrix::from_string_lines(
+            "\
+..........
+..........
+.########.
+..........
+..........
+",
+        );
+        let open = |p: Point| -> Vec<(Point, isize)> {
+            m.neighbors4(p)
+                .into_iter()
+                .filter(|(_, c)| **c == '.')
+                .map(|(np, _)| (np, 1))
+                .collect()
+        };
+        let (from, to) = (point(4, 0), point(4, 4));
+        let mut expanded = 0;
+        let mut counted = |p| {
+            expanded += 1;
+            open(p)
+        };
+        assert_eq!(shortest_distance(from, to, &mut counted), Some(12));
+        let dijkstra = expanded;
+
+        expanded = 0;
+        let mut counted = |p| {
+            expanded += 1;
+            open(p)
+        };
+        let path = astar_path_fn(from, |&p| p == to, &mut counted, manhattan_to(to)).unwrap();
+        assert_eq!(path.dist, 12);
+        assert_eq!(path.steps.len(), 13);
+        assert!(expanded < dijkstra, "{} >= {}", expanded, dijkstra);
+    }
+
+    #[test]
+    fn bounded() {
+        // An endless line, where every point links to the next.
+        let mut line = |p: isize| vec![(p - 1, 1), (p + 1, 1)];
+        let unlimited = bounded_search(0, |&p| p == 5, &mut line, |_| 0, Limits::default());
+        assert_eq!(unlimited.path.unwrap().dist, 5);
+        assert_eq!(unlimited.stats.expanded, 9);
+        assert!(unlimited.stats.queue_peak >= 2);
+
+        let limits = Limits {
+            max_dist: Some(4),
+            max_expanded: None,
+        };
+        let too_short = bounded_search(0, |&p| p == 5, &mut line, |_| 0, limits);
+        assert_eq!(too_short.path, None);
+        assert_eq!(too_short.stats.expanded, 9);
+
+        let limits = Limits {
+            max_dist: None,
+            max_expanded: Some(100),
+        };
+        let never = bounded_search(0, |_| false, &mut line, |_| 0, limits);
+        assert_eq!(never.path, None);
+        assert_eq!(never.stats.expanded, 100);
+    }
+
+    #[test]
+    fn other_costs() {
+        // Unsigned costs work, with the default heuristic.
+        let mut line = |p: u32| vec![(p + 1, 2u64)];
+        assert_eq!(shortest_distance(0, 5, &mut line), Some(10u64));
+
+        // Diagonal steps cost the square root of 2.
+        let mut grid = |p: (i32, i32)| {
+            let mut v = Vec::new();
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    if dx != 0 || dy != 0 {
+                        let cost = if dx != 0 && dy != 0 { 2f64.sqrt() } else { 1.0 };
+                        v.push(((p.0 + dx, p.1 + dy), F64Cost(cost)));
+                    }
+                }
+            }
+            v
+        };
+        let d = shortest_distance((0, 0), (3, 1), &mut grid).unwrap();
+        assert!((d.0 - (2.0 + 2f64.sqrt())).abs() < 1e-9);
+
+        // Fewest doors first, then shortest: going through 2 costs a door.
+        let mut doors = |p: u8| match p {
+            0 => vec![(1, (0, 10)), (2, (1, 1))],
+            1 | 2 => vec![(3, (0, 1))],
+            _ => vec![],
+        };
+        let path = shortest_path(0, 3, &mut doors).unwrap();
+        assert_eq!(path.positions(), vec![0, 1, 3]);
+        assert_eq!(path.dist, (0, 11));
+    }
+
+    #[test]
+    fn breadth_first() {
+        use crate::{point, Matrix};
+
+        let m = Matrix::from_string_lines(
+            "\
+.....
+.###.
+.#...
+.#.##
+",
+        );
+        let mut open = |p: Point| -> Vec<Point> {
+            m.neighbors4(p)
+                .into_iter()
+                .filter(|(_, c)| **c == '.')
+                .map(|(np, _)| np)
+                .collect()
+        };
+        let dist = bfs_distances(Some(point(0, 0)), &mut open);
+        assert_eq!(dist.len(), 13);
+        assert_eq!(dist[&point(2, 3)], 9);
+        assert!(!dist.contains_key(&point(1, 1)));
+
+        let both = bfs_distances(vec![point(0, 0), point(2, 3)], &mut open);
+        assert_eq!(both[&point(4, 0)], 4);
+        assert_eq!(both[&point(4, 2)], 3);
+
+        let path = bfs_path(point(0, 3), point(2, 3), &mut open).unwrap();
+        assert_eq!(path.dist, 12);
+        assert_eq!(path.steps.len(), 13);
+        assert_eq!(path.steps[1], (point(0, 2), 1));
+
+        let nearest = bfs_path_fn(vec![point(0, 3), point(2, 3)], |p| p.y == 0, &mut open);
+        assert_eq!(nearest.unwrap().positions()[0], point(0, 3));
+        assert_eq!(bfs_path(point(0, 0), point(1, 1), &mut open), None);
+    }
+
+    #[test]
+    fn distances_to_all() {
+        let dist = distances_from(0, &mut diamond);
+        assert_eq!(dist.len(), 5);
+        assert_eq!(dist[&0], 0);
+        assert_eq!(dist[&3], 3);
+        assert_eq!(dist[&4], 4);
+    }
+
+    #[test]
+    fn distance_table() {
+        let nodes = [('a', 0), ('b', 3), ('c', 4)];
+        let table = DistanceTable::from_points(&nodes, &mut diamond);
+        assert_eq!(table.labels(), &['a', 'b', 'c']);
+        assert_eq!(table.get('a', 'c'), Some(4));
+        assert_eq!(table.get('c', 'b'), Some(1));
+        assert_eq!(table.get('b', 'b'), Some(0));
+        assert_eq!(table.get('a', 'z'), None);
+        assert_eq!(
+            table.row('b').collect::<Vec<_>>(),
+            vec![('a', 3), ('b', 0), ('c', 1)]
+        );
+
+        let all = DistanceTable::floyd_warshall(vec![0, 1, 2, 3, 4], &mut diamond);
+        let searched =
+            DistanceTable::from_points(&[(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)], &mut diamond);
+        for from in 0..5 {
+            for to in 0..5 {
+                assert_eq!(all.get(from, to), searched.get(from, to));
+            }
         }
+
+        // Only some nodes are connected.
+        let one_way = DistanceTable::floyd_warshall(vec![1, 2, 3], &mut |p: u8| match p {
+            1 => vec![(2, 5), (2, 3)],
+            _ => vec![],
+        });
+        assert_eq!(one_way.get(1, 2), Some(3));
+        assert_eq!(one_way.get(2, 1), None);
+        assert_eq!(one_way.row(1).count(), 2);
+    }
+
+    #[test]
+    fn distance_cache() {
+        let mut searches = 0;
+        let mut cache = DistanceCache::new(|p| {
+            searches += 1;
+            diamond(p)
+        });
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(0, 4), Some(4));
+        assert_eq!(cache.get(0, 3), Some(3));
+        assert_eq!(cache.get(4, 0), Some(4));
+        assert_eq!(cache.len(), 2);
+        drop(cache);
+        assert_eq!(searches, 10);
+    }
+
+    #[test]
+    fn other_strategies() {
+        for from in 0..5 {
+            for to in 0..5 {
+                let expected = shortest_distance(from, to, &mut diamond);
+                assert_eq!(bidirectional_distance(from, to, &mut diamond), expected);
+                let path = ida_star(from, |&p| p == to, &mut diamond, |_| 0).unwrap();
+                assert_eq!(Some(path.dist), expected);
+                assert_eq!(path.steps[0], (from, 0));
+                assert_eq!(path.dest(), to);
+            }
+        }
+        // Two islands: 0 and 1, and 2 and 3.
+        let mut islands = |p: u8| vec![(p ^ 1, 1)];
+        assert_eq!(bidirectional_distance(0, 2, &mut islands), None);
+        assert!(ida_star(0, |&p| p == 2, &mut islands, |_| 0).is_none());
+
+        // An endless line needs a heuristic to stop IDA* wandering away.
+        let mut line = |p: isize| vec![(p - 1, 1), (p + 1, 1)];
+        let path = ida_star(0, |&p| p == 5, &mut line, |p| (5 - p).abs()).unwrap();
+        assert_eq!(path.positions(), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(bidirectional_distance(0, 5, &mut line), Some(5));
     }
 }
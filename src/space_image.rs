@@ -0,0 +1,347 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The Space Image Format from day 8.
+//!
+//! An image is a string of digits, making up a stack of layers that are
+//! each `width * height` digits, in row-major order. The first layer is in
+//! front. A `Palette` says which digit, if any, is transparent, and what
+//! shade of gray each digit is.
+//!
+//! Images can be written as plain PGM files, with one image per layer, and
+//! read back.
+
+use std::fmt;
+
+use crate::{point, Matrix};
+
+/// Digits run from 0 to 9.
+const DIGITS: usize = 10;
+
+/// How to show the digits in an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    /// Layers behind show through this digit.
+    pub transparent: Option<u8>,
+    /// Gray level, from 0 for black to 255 for white, for each digit.
+    pub gray: [u8; DIGITS],
+}
+
+impl Palette {
+    /// The day 8 palette: 0 is black, 1 is white, and 2 is transparent.
+    pub fn standard() -> Palette {
+        let mut gray = [0; DIGITS];
+        gray[1] = 255;
+        Palette {
+            transparent: Some(2),
+            gray,
+        }
+    }
+
+    /// Digits from 0 for black to 9 for white, with none transparent.
+    pub fn grayscale() -> Palette {
+        let mut gray = [0; DIGITS];
+        for (d, g) in gray.iter_mut().enumerate() {
+            *g = (d * 255 / (DIGITS - 1)) as u8;
+        }
+        Palette {
+            transparent: None,
+            gray,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseImageError {
+    /// Something other than a digit, at this position.
+    BadDigit(usize, char),
+    /// The number of digits isn't a whole number of layers.
+    PartialLayer { digits: usize, layer_size: usize },
+    /// Width or height is zero, or there are no digits.
+    Empty,
+    /// A PGM file was malformed.
+    Pgm(String),
+}
+
+impl fmt::Display for ParseImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseImageError::BadDigit(pos, c) => write!(f, "bad digit {:?} at {}", c, pos),
+            ParseImageError::PartialLayer { digits, layer_size } => write!(
+                f,
+                "{} digits isn't a whole number of layers of {}",
+                digits, layer_size
+            ),
+            ParseImageError::Empty => f.write_str("empty image"),
+            ParseImageError::Pgm(msg) => write!(f, "bad PGM: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ParseImageError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpaceImage {
+    width: usize,
+    height: usize,
+    /// All the layers, front first, each `width * height` long.
+    digits: Vec<u8>,
+}
+
+impl SpaceImage {
+    /// Parse a string of digits. Whitespace at either end is ignored.
+    pub fn parse(s: &str, width: usize, height: usize) -> Result<SpaceImage, ParseImageError> {
+        let digits = s
+            .trim()
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                c.to_digit(10)
+                    .map(|d| d as u8)
+                    .ok_or(ParseImageError::BadDigit(i, c))
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        SpaceImage::from_digits(digits, width, height)
+    }
+
+    fn from_digits(
+        digits: Vec<u8>,
+        width: usize,
+        height: usize,
+    ) -> Result<SpaceImage, ParseImageError> {
+        let layer_size = width * height;
+        if layer_size == 0 || digits.is_empty() {
+            return Err(ParseImageError::Empty);
+        }
+        if !digits.len().is_multiple_of(layer_size) {
+            return Err(ParseImageError::PartialLayer {
+                digits: digits.len(),
+                layer_size,
+            });
+        }
+        Ok(SpaceImage {
+            width,
+            height,
+            digits,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.digits.len() / self.layer_size()
+    }
+
+    fn layer_size(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// The digits in one layer, in row-major order.
+    pub fn layer(&self, l: usize) -> &[u8] {
+        assert!(l < self.layer_count());
+        let sz = self.layer_size();
+        &self.digits[(sz * l)..(sz * (l + 1))]
+    }
+
+    /// How many times each digit occurs in one layer.
+    pub fn layer_stats(&self, l: usize) -> [usize; DIGITS] {
+        let mut counts = [0; DIGITS];
+        for &d in self.layer(l) {
+            counts[d as usize] += 1;
+        }
+        counts
+    }
+
+    /// How many times digit `d` occurs in layer `l`.
+    pub fn count_in_layer(&self, d: u8, l: usize) -> usize {
+        bytecount::count(self.layer(l), d)
+    }
+
+    /// Statistics for every layer, front first.
+    pub fn stats(&self) -> Vec<[usize; DIGITS]> {
+        (0..self.layer_count())
+            .map(|l| self.layer_stats(l))
+            .collect()
+    }
+
+    /// Flatten the layers, so that each pixel is the frontmost digit that
+    /// isn't transparent.
+    ///
+    /// Pixels that are transparent all the way through stay transparent.
+    pub fn composite(&self, palette: &Palette) -> Matrix<u8> {
+        let mut m = Matrix::new(self.width, self.height, 0);
+        for (i, p) in m.iter_points().collect::<Vec<_>>().into_iter().enumerate() {
+            let mut layers = self.digits.iter().skip(i).step_by(self.layer_size());
+            let first = *layers.next().unwrap();
+            m[p] = if Some(first) == palette.transparent {
+                layers
+                    .find(|d| Some(**d) != palette.transparent)
+                    .copied()
+                    .unwrap_or(first)
+            } else {
+                first
+            };
+        }
+        m
+    }
+
+    /// Composite and draw as a plain PGM, in the palette's shades of gray.
+    pub fn composite_pgm(&self, palette: &Palette) -> String {
+        let m = self.composite(palette);
+        let mut s = format!("P2\n{} {}\n255\n", self.width, self.height);
+        for y in 0..self.height {
+            let row: Vec<String> = (0..self.width)
+                .map(|x| palette.gray[m[point(x as isize, y as isize)] as usize].to_string())
+                .collect();
+            s.push_str(&row.join(" "));
+            s.push('\n');
+        }
+        s
+    }
+
+    /// Write every layer, front first, as a plain PGM image with a maximum
+    /// value of 9, so that pixel values are the digits.
+    pub fn to_pgm(&self) -> String {
+        let mut s = String::new();
+        for l in 0..self.layer_count() {
+            s.push_str(&format!("P2\n{} {}\n9\n", self.width, self.height));
+            for row in self.layer(l).chunks(self.width) {
+                let row: Vec<String> = row.iter().map(|d| d.to_string()).collect();
+                s.push_str(&row.join(" "));
+                s.push('\n');
+            }
+        }
+        s
+    }
+
+    /// Read back images written by `to_pgm`, as one layer per image.
+    ///
+    /// All the images must be the same size, and have a maximum value of
+    /// at most 9. Comments are not supported.
+    pub fn from_pgm(s: &str) -> Result<SpaceImage, ParseImageError> {
+        let bad = |msg: &str| ParseImageError::Pgm(msg.to_owned());
+        let mut words = s.split_whitespace().peekable();
+        let mut size = None;
+        let mut digits = Vec::new();
+        while words.peek().is_some() {
+            if words.next() != Some("P2") {
+                return Err(bad("expected P2"));
+            }
+            let mut number = || -> Result<usize, ParseImageError> {
+                words
+                    .next()
+                    .ok_or_else(|| bad("truncated"))?
+                    .parse()
+                    .map_err(|_| bad("expected a number"))
+            };
+            let (w, h, maxval) = (number()?, number()?, number()?);
+            if maxval >= DIGITS {
+                return Err(bad("maximum value is more than 9"));
+            }
+            if *size.get_or_insert((w, h)) != (w, h) {
+                return Err(bad("images are different sizes"));
+            }
+            for _ in 0..(w * h) {
+                let v = number()?;
+                if v > maxval {
+                    return Err(bad("value more than the maximum"));
+                }
+                digits.push(v as u8);
+            }
+        }
+        let (w, h) = size.ok_or(ParseImageError::Empty)?;
+        SpaceImage::from_digits(digits, w, h)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn example_composite() {
+        let im = SpaceImage::parse("0222112222120000\n", 2, 2).unwrap();
+        assert_eq!(im.layer_count(), 4);
+        assert_eq!(im.layer(1), &[1, 1, 2, 2]);
+        assert_eq!(im.layer_stats(0), [1, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(im.count_in_layer(2, 2), 3);
+        let m = im.composite(&Palette::standard());
+        assert_eq!(m.values().copied().collect::<Vec<u8>>(), vec![0, 1, 1, 0]);
+        assert_eq!(
+            im.composite_pgm(&Palette::standard()),
+            "P2\n2 2\n255\n0 255\n255 0\n"
+        );
+    }
+
+    #[test]
+    fn other_palettes() {
+        let im = SpaceImage::parse("5092", 2, 1).unwrap();
+        let m = im.composite(&Palette::grayscale());
+        assert_eq!(m.values().copied().collect::<Vec<u8>>(), vec![5, 0]);
+        let see_through_zero = Palette {
+            transparent: Some(0),
+            ..Palette::grayscale()
+        };
+        let m = im.composite(&see_through_zero);
+        assert_eq!(m.values().copied().collect::<Vec<u8>>(), vec![5, 2]);
+        assert_eq!(
+            im.composite_pgm(&see_through_zero),
+            "P2\n2 1\n255\n141 56\n"
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            SpaceImage::parse("0120", 3, 1),
+            Err(ParseImageError::PartialLayer {
+                digits: 4,
+                layer_size: 3
+            })
+        );
+        assert_eq!(
+            SpaceImage::parse("01x0", 2, 1),
+            Err(ParseImageError::BadDigit(2, 'x'))
+        );
+        assert_eq!(SpaceImage::parse("", 2, 1), Err(ParseImageError::Empty));
+        assert_eq!(SpaceImage::parse("01", 0, 1), Err(ParseImageError::Empty));
+    }
+
+    #[test]
+    fn pgm_round_trip() {
+        let im = SpaceImage::parse("012345678901", 3, 2).unwrap();
+        let pgm = im.to_pgm();
+        assert!(pgm.starts_with("P2\n3 2\n9\n0 1 2\n3 4 5\nP2\n"));
+        assert_eq!(SpaceImage::from_pgm(&pgm), Ok(im));
+
+        assert_eq!(
+            SpaceImage::from_pgm("P2\n1 1\n9\n0\nP2\n2 1\n9\n0 0\n"),
+            Err(ParseImageError::Pgm(
+                "images are different sizes".to_owned()
+            ))
+        );
+        assert_eq!(
+            SpaceImage::from_pgm("P2\n1 1\n255\n0\n"),
+            Err(ParseImageError::Pgm(
+                "maximum value is more than 9".to_owned()
+            ))
+        );
+    }
+}
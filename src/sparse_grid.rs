@@ -0,0 +1,196 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A sparse, unbounded 2d grid.
+//!
+//! Unlike a `Matrix`, any point can be set, including negative coordinates,
+//! and only the points that have been set are stored. Points that haven't
+//! been set read as the grid's default value.
+
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+use std::ops::Index;
+
+use crate::{point, Matrix, Point};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SparseGrid<T> {
+    cells: BTreeMap<Point, T>,
+    default: T,
+}
+
+impl<T: Clone> SparseGrid<T> {
+    /// Make an empty grid, where every point reads as `default`.
+    pub fn new(default: T) -> SparseGrid<T> {
+        SparseGrid {
+            cells: BTreeMap::new(),
+            default,
+        }
+    }
+
+    /// Copy every cell of a matrix, with its top-left corner at `origin`.
+    pub fn from_matrix(m: &Matrix<T>, origin: Point, default: T) -> SparseGrid<T> {
+        let mut grid = SparseGrid::new(default);
+        for p in m.iter_points() {
+            grid.set(point(p.x + origin.x, p.y + origin.y), m[p].clone());
+        }
+        grid
+    }
+
+    pub fn default_value(&self) -> &T {
+        &self.default
+    }
+
+    /// The value at `p`, or the default if it was never set.
+    pub fn get(&self, p: Point) -> &T {
+        self.cells.get(&p).unwrap_or(&self.default)
+    }
+
+    /// Set the value at `p`, returning the previous value if it was set.
+    pub fn set(&mut self, p: Point, v: T) -> Option<T> {
+        self.cells.insert(p, v)
+    }
+
+    /// Forget the value at `p`, so that it reads as the default again.
+    pub fn remove(&mut self, p: Point) -> Option<T> {
+        self.cells.remove(&p)
+    }
+
+    /// True if `p` has been set.
+    pub fn contains(&self, p: Point) -> bool {
+        self.cells.contains_key(&p)
+    }
+
+    /// Number of points that have been set.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Iterate the points that have been set, in row-major order.
+    pub fn iter(&self) -> btree_map::Iter<'_, Point, T> {
+        self.cells.iter()
+    }
+
+    /// Iterate the points that have been set, in row-major order.
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.cells.keys().copied()
+    }
+
+    /// The top-left and bottom-right corners of every point that has been
+    /// set, or None if the grid is empty.
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        let mut points = self.cells.keys();
+        let first = *points.next()?;
+        Some(points.fold((first, first), |(min, max), p| {
+            (
+                point(min.x.min(p.x), min.y.min(p.y)),
+                point(max.x.max(p.x), max.y.max(p.y)),
+            )
+        }))
+    }
+
+    /// Copy into a matrix covering the bounding box, with unset points
+    /// filled with the default.
+    ///
+    /// Returns the matrix and the position of its top-left corner in the
+    /// grid. An empty grid gives a 0x0 matrix.
+    pub fn to_matrix(&self) -> (Matrix<T>, Point) {
+        let (min, max) = match self.bounds() {
+            Some(b) => b,
+            None => return (Matrix::new(0, 0, self.default.clone()), point(0, 0)),
+        };
+        let mut m = Matrix::new(
+            (max.x - min.x + 1) as usize,
+            (max.y - min.y + 1) as usize,
+            self.default.clone(),
+        );
+        for (p, v) in &self.cells {
+            m[point(p.x - min.x, p.y - min.y)] = v.clone();
+        }
+        (m, min)
+    }
+
+    /// Draw the bounding box as lines of text, one character per point.
+    pub fn render<F: Fn(&T) -> char>(&self, to_char: F) -> String {
+        let mut s = String::new();
+        if let Some((min, max)) = self.bounds() {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    s.push(to_char(self.get(point(x, y))));
+                }
+                s.push('\n');
+            }
+        }
+        s
+    }
+}
+
+impl SparseGrid<char> {
+    /// Draw the bounding box as lines of text.
+    pub fn to_string_lines(&self) -> String {
+        self.render(|c| *c)
+    }
+}
+
+impl<T: Clone> Index<Point> for SparseGrid<T> {
+    type Output = T;
+    fn index(&self, p: Point) -> &T {
+        self.get(p)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn set_anywhere() {
+        let mut g = SparseGrid::new('.');
+        assert!(g.is_empty());
+        assert_eq!(g.bounds(), None);
+        assert_eq!(g.to_string_lines(), "");
+        g.set(point(-2, -1), 'a');
+        g.set(point(1, 1), 'b');
+        assert_eq!(g.set(point(0, -1), 'c'), None);
+        assert_eq!(g.set(point(0, -1), 'd'), Some('c'));
+        assert_eq!(g.len(), 3);
+        assert_eq!(g[point(1, 1)], 'b');
+        assert_eq!(g[point(5, 5)], '.');
+        assert!(!g.contains(point(5, 5)));
+        assert_eq!(g.bounds(), Some((point(-2, -1), point(1, 1))));
+        assert_eq!(g.to_string_lines(), "a.d.\n....\n...b\n");
+        assert_eq!(
+            g.points().collect::<Vec<Point>>(),
+            vec![point(-2, -1), point(0, -1), point(1, 1)]
+        );
+        assert_eq!(g.remove(point(-2, -1)), Some('a'));
+        assert_eq!(g.to_string_lines(), "d.\n..\n.b\n");
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        let m = Matrix::from_string_lines("ab\ncd\n");
+        let g = SparseGrid::from_matrix(&m, point(-5, 3), ' ');
+        assert_eq!(g[point(-4, 4)], 'd');
+        assert_eq!(g.bounds(), Some((point(-5, 3), point(-4, 4))));
+        let (m2, origin) = g.to_matrix();
+        assert_eq!(origin, point(-5, 3));
+        assert!(m2 == m);
+        assert_eq!(g.render(|c| c.to_ascii_uppercase()), "AB\nCD\n");
+    }
+}
@@ -0,0 +1,705 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Offline simulation of the day 21 springdroid, and a search for
+//! springscript programs that get it across known hulls.
+//!
+//! The droid starts on the leftmost square of a hull. Every turn it runs the
+//! program with fresh `T` and `J` registers, and then either jumps four
+//! squares forward (if `J` is true) or walks one square. If it lands on a
+//! hole it falls in.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// The longest program the springdroid will accept.
+pub const MAX_PROGRAM_LEN: usize = 15;
+
+/// How far a jump carries the droid.
+const JUMP_LEN: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Sensors `A` through `D`.
+    Walk,
+    /// Sensors `A` through `I`.
+    Run,
+}
+
+impl Mode {
+    /// Number of squares the droid can see ahead.
+    pub fn sensors(self) -> usize {
+        match self {
+            Mode::Walk => 4,
+            Mode::Run => 9,
+        }
+    }
+
+    /// The command that ends the program and starts the droid.
+    pub fn command(self) -> &'static str {
+        match self {
+            Mode::Walk => "WALK",
+            Mode::Run => "RUN",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    And,
+    Or,
+    Not,
+}
+
+/// A springscript register: a read-only sensor, numbered from 0 for `A`, or
+/// one of the writable `T` and `J` registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reg {
+    Sensor(u8),
+    T,
+    J,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Insn {
+    pub op: Op,
+    pub x: Reg,
+    pub y: Reg,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    pub insns: Vec<Insn>,
+}
+
+#[derive(Debug)]
+pub struct ParseProgramError {
+    pub line: String,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Not => "NOT",
+        })
+    }
+}
+
+impl fmt::Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reg::Sensor(i) => write!(f, "{}", (b'A' + i) as char),
+            Reg::T => f.write_str("T"),
+            Reg::J => f.write_str("J"),
+        }
+    }
+}
+
+impl fmt::Display for Insn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.op, self.x, self.y)
+    }
+}
+
+impl fmt::Display for Program {
+    /// Format as springscript, one instruction per line, without the final
+    /// `WALK` or `RUN`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for insn in &self.insns {
+            writeln!(f, "{}", insn)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Reg {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Reg, ()> {
+        match s.as_bytes() {
+            b"T" => Ok(Reg::T),
+            b"J" => Ok(Reg::J),
+            [c @ b'A'..=b'I'] => Ok(Reg::Sensor(c - b'A')),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for Insn {
+    type Err = ParseProgramError;
+
+    fn from_str(s: &str) -> Result<Insn, ParseProgramError> {
+        let err = || ParseProgramError { line: s.to_owned() };
+        let words: Vec<&str> = s.split_whitespace().collect();
+        if words.len() != 3 {
+            return Err(err());
+        }
+        let op = match words[0] {
+            "AND" => Op::And,
+            "OR" => Op::Or,
+            "NOT" => Op::Not,
+            _ => return Err(err()),
+        };
+        let x = words[1].parse().map_err(|_| err())?;
+        let y = words[2].parse().map_err(|_| err())?;
+        if let Reg::Sensor(_) = y {
+            return Err(err());
+        }
+        Ok(Insn { op, x, y })
+    }
+}
+
+impl FromStr for Program {
+    type Err = ParseProgramError;
+
+    /// Parse springscript text. Blank lines and a trailing `WALK` or `RUN`
+    /// are ignored.
+    fn from_str(s: &str) -> Result<Program, ParseProgramError> {
+        let insns = s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && *l != "WALK" && *l != "RUN")
+            .map(Insn::from_str)
+            .collect::<Result<Vec<Insn>, _>>()?;
+        Ok(Program { insns })
+    }
+}
+
+impl Program {
+    /// The program as springdroid input, ending with the mode command.
+    pub fn to_script(&self, mode: Mode) -> String {
+        format!("{}{}\n", self, mode.command())
+    }
+
+    /// Evaluate the program for one turn, given sensor readings with bit `i`
+    /// set if sensor `i` sees ground. Returns whether the droid jumps.
+    pub fn eval(&self, sensors: u16) -> bool {
+        let mut t = false;
+        let mut j = false;
+        for insn in &self.insns {
+            let x = match insn.x {
+                Reg::Sensor(i) => sensors & (1 << i) != 0,
+                Reg::T => t,
+                Reg::J => j,
+            };
+            let y = match insn.y {
+                Reg::T => &mut t,
+                Reg::J => &mut j,
+                Reg::Sensor(_) => panic!("can't write to sensor in {}", insn),
+            };
+            match insn.op {
+                Op::And => *y &= x,
+                Op::Or => *y |= x,
+                Op::Not => *y = !x,
+            }
+        }
+        j
+    }
+}
+
+/// A row of hull squares, true where there is ground.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hull {
+    ground: Vec<bool>,
+}
+
+impl Hull {
+    /// Parse a hull drawn as `#` for ground and `.` for holes.
+    pub fn from_string(s: &str) -> Hull {
+        Hull {
+            ground: s
+                .trim()
+                .chars()
+                .map(|c| match c {
+                    '#' => true,
+                    '.' => false,
+                    other => panic!("unexpected hull character {:?}", other),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ground.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ground.is_empty()
+    }
+
+    /// True if there is ground at `x`. Everything past the drawn hull is
+    /// assumed to be solid.
+    pub fn is_ground(&self, x: usize) -> bool {
+        self.ground.get(x).copied().unwrap_or(true)
+    }
+
+    /// The sensor bits seen by a droid standing at `x`.
+    pub fn sensors(&self, x: usize, mode: Mode) -> u16 {
+        (0..mode.sensors())
+            .filter(|i| self.is_ground(x + 1 + i))
+            .fold(0, |acc, i| acc | (1 << i))
+    }
+
+    /// Replay a droid crossing this hull, deciding at each position whether
+    /// to jump.
+    ///
+    /// Returns the positions the droid stood on, or `Err` with the position
+    /// of the hole it fell into.
+    pub fn replay<F>(&self, mode: Mode, mut jump: F) -> Result<Vec<usize>, usize>
+    where
+        F: FnMut(u16) -> bool,
+    {
+        let mut x = 0;
+        let mut trail = vec![x];
+        while x < self.len() {
+            x += if jump(self.sensors(x, mode)) {
+                JUMP_LEN
+            } else {
+                1
+            };
+            if !self.is_ground(x) {
+                return Err(x);
+            }
+            trail.push(x);
+        }
+        Ok(trail)
+    }
+
+    /// Which squares the droid could possibly stand on, whatever it decides
+    /// along the way.
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut r = vec![false; self.len()];
+        for x in 0..self.len() {
+            r[x] = self.is_ground(x) && (x == 0 || r[x - 1] || (x >= JUMP_LEN && r[x - JUMP_LEN]));
+        }
+        r
+    }
+
+    /// True if the program gets the droid across this hull.
+    pub fn survives(&self, program: &Program, mode: Mode) -> bool {
+        self.replay(mode, |s| program.eval(s)).is_ok()
+    }
+}
+
+impl fmt::Display for Hull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &g in &self.ground {
+            f.write_str(if g { "#" } else { "." })?;
+        }
+        Ok(())
+    }
+}
+
+/// Extract the distinct hulls drawn in the output of a failed springdroid run.
+///
+/// The droid's view is drawn as a series of frames, with the hull as the
+/// bottom line of each: the only lines that start with ground.
+pub fn parse_failures(text: &str) -> Vec<Hull> {
+    let mut hulls: Vec<Hull> = Vec::new();
+    for l in text.lines().map(str::trim) {
+        if l.starts_with('#') && l.chars().all(|c| c == '#' || c == '.') {
+            let hull = Hull::from_string(l);
+            if !hulls.contains(&hull) {
+                hulls.push(hull);
+            }
+        }
+    }
+    hulls
+}
+
+/// Truth table over the distinct sensor readings seen on the hulls.
+type Table = Vec<u64>;
+
+/// How many distinct states the exhaustive search will visit before
+/// giving up.
+pub const DEFAULT_MAX_STATES: usize = 20_000;
+
+/// Find a program that gets the droid across all of `hulls`, preferring a
+/// proven-shortest one from `search`, but falling back to climbs by `evolve`
+/// from a few different seeds if the exhaustive search gets too big.
+pub fn find_program(mode: Mode, hulls: &[Hull]) -> Option<Program> {
+    search(mode, hulls, MAX_PROGRAM_LEN, DEFAULT_MAX_STATES)
+        .or_else(|| (1..=20).find_map(|seed| evolve(mode, hulls, MAX_PROGRAM_LEN, seed, 1)))
+}
+
+/// Find a shortest program that gets the droid across all of `hulls`,
+/// if there is one of at most `max_len` instructions.
+///
+/// The program only ever sees the sensor readings from the squares the
+/// droid can stand on, so programs are compared by the truth tables of `T`
+/// and `J` over just those readings. This is a breadth-first search over
+/// those pairs of tables, so many equivalent programs collapse into one
+/// state and the first passing program found is as short as possible.
+///
+/// The number of states still grows exponentially with the program length,
+/// so this gives up and returns None after visiting `max_states`.
+pub fn search(mode: Mode, hulls: &[Hull], max_len: usize, max_states: usize) -> Option<Program> {
+    // Number each distinct sensor reading, and record which reading is seen
+    // at every square of ground.
+    let mut reading_index = HashMap::<u16, usize>::new();
+    let mut readings = Vec::<u16>::new();
+    let mut hull_readings = Vec::<Vec<usize>>::new();
+    for hull in hulls {
+        let reachable = hull.reachable();
+        let mut hr = Vec::with_capacity(hull.len());
+        for (x, &r) in reachable.iter().enumerate() {
+            if !r {
+                hr.push(usize::MAX);
+                continue;
+            }
+            let s = hull.sensors(x, mode);
+            let i = *reading_index.entry(s).or_insert_with(|| {
+                readings.push(s);
+                readings.len() - 1
+            });
+            hr.push(i);
+        }
+        hull_readings.push(hr);
+    }
+    let words = readings.len().div_ceil(64);
+    let bit = |t: &Table, i: usize| t[i / 64] & (1 << (i % 64)) != 0;
+    let passes = |j: &Table| {
+        hulls
+            .iter()
+            .zip(&hull_readings)
+            .all(|(hull, hr)| replay_indexed(hull, hr, |i| bit(j, i)))
+    };
+
+    let mut sensor_tables: Vec<Table> = Vec::new();
+    for sensor in 0..mode.sensors() {
+        let mut t = vec![0u64; words];
+        for (i, s) in readings.iter().enumerate() {
+            if s & (1 << sensor) != 0 {
+                t[i / 64] |= 1 << (i % 64);
+            }
+        }
+        sensor_tables.push(t);
+    }
+    let mut all_ones = vec![!0u64; words];
+    let spare_bits = words * 64 - readings.len();
+    if spare_bits > 0 {
+        all_ones[words - 1] >>= spare_bits;
+    }
+
+    let choices = all_insns(mode);
+
+    // Every state ever reached, along with its parent and the instruction
+    // that reached it, so that the program can be reconstructed.
+    let empty = vec![0u64; words];
+    let mut states: Vec<(Table, Table, usize, Option<Insn>)> =
+        vec![(empty.clone(), empty.clone(), 0, None)];
+    let mut seen: HashSet<(Table, Table)> = HashSet::new();
+    seen.insert((empty.clone(), empty));
+    let program_to = |states: &[(Table, Table, usize, Option<Insn>)], mut i: usize| {
+        let mut insns = Vec::new();
+        while let Some(insn) = states[i].3 {
+            insns.push(insn);
+            i = states[i].2;
+        }
+        insns.reverse();
+        Program { insns }
+    };
+    if passes(&states[0].1) {
+        return Some(Program::default());
+    }
+    let mut frontier = vec![0];
+    for _len in 0..max_len {
+        let mut next_frontier = Vec::new();
+        for &si in &frontier {
+            for insn in &choices {
+                let (t, j) = (&states[si].0, &states[si].1);
+                let x = match insn.x {
+                    Reg::Sensor(i) => &sensor_tables[i as usize],
+                    Reg::T => t,
+                    Reg::J => j,
+                };
+                let y = match insn.y {
+                    Reg::T => t,
+                    _ => j,
+                };
+                let result: Table = (0..words)
+                    .map(|w| match insn.op {
+                        Op::And => x[w] & y[w],
+                        Op::Or => x[w] | y[w],
+                        Op::Not => !x[w] & all_ones[w],
+                    })
+                    .collect();
+                let (nt, nj) = match insn.y {
+                    Reg::T => (result, j.clone()),
+                    _ => (t.clone(), result),
+                };
+                if seen.insert((nt.clone(), nj.clone())) {
+                    if states.len() >= max_states {
+                        return None;
+                    }
+                    let found = passes(&nj);
+                    states.push((nt, nj, si, Some(*insn)));
+                    if found {
+                        return Some(program_to(&states, states.len() - 1));
+                    }
+                    next_frontier.push(states.len() - 1);
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+    None
+}
+
+/// Replay a hull where the reading at each square has been precomputed.
+fn replay_indexed<F>(hull: &Hull, readings: &[usize], jump: F) -> bool
+where
+    F: Fn(usize) -> bool,
+{
+    let mut x = 0;
+    while x < hull.len() {
+        x += if jump(readings[x]) { JUMP_LEN } else { 1 };
+        if !hull.is_ground(x) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Every instruction that's valid in the given mode.
+fn all_insns(mode: Mode) -> Vec<Insn> {
+    let mut v = Vec::new();
+    for &op in &[Op::And, Op::Or, Op::Not] {
+        for x in (0..mode.sensors() as u8)
+            .map(Reg::Sensor)
+            .chain(vec![Reg::T, Reg::J])
+        {
+            for &y in &[Reg::T, Reg::J] {
+                v.push(Insn { op, x, y });
+            }
+        }
+    }
+    v
+}
+
+/// How far the program gets across all the hulls: the sum of the squares
+/// reached on each, counting a crossing as reaching one past the end.
+fn progress(program: &Program, mode: Mode, hulls: &[Hull]) -> usize {
+    hulls
+        .iter()
+        .map(|hull| match hull.replay(mode, |s| program.eval(s)) {
+            Ok(_) => hull.len() + 1,
+            Err(x) => x,
+        })
+        .sum()
+}
+
+fn passes_all(program: &Program, mode: Mode, hulls: &[Hull]) -> bool {
+    hulls.iter().all(|hull| hull.survives(program, mode))
+}
+
+/// Deterministic xorshift generator, good enough to drive `evolve`.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % (n as u64)) as usize
+    }
+}
+
+/// Search for a program that crosses all the `hulls` by hill-climbing from
+/// random programs.
+///
+/// Each step makes a random change to one instruction (replacing, inserting
+/// or deleting it) and keeps the change if the droid gets at least as far
+/// across the hulls, so that the program can drift across plateaus. When
+/// nothing has improved for a while, start again from another random
+/// program.
+///
+/// Unlike `search` this can find long programs quickly, but they are not
+/// necessarily the shortest. Passing programs are pruned of any
+/// instructions they can do without, and the shortest found in `restarts`
+/// climbs is returned. There are no programs to try if `max_len` is 0.
+pub fn evolve(
+    mode: Mode,
+    hulls: &[Hull],
+    max_len: usize,
+    seed: u64,
+    restarts: usize,
+) -> Option<Program> {
+    const MAX_STALE: usize = 5_000;
+    if max_len == 0 {
+        return None;
+    }
+    let choices = all_insns(mode);
+    let mut rng = Rng(seed.max(1));
+    let mut best: Option<Program> = None;
+    for _ in 0..restarts {
+        let mut current = Program {
+            insns: (0..=rng.below(max_len))
+                .map(|_| choices[rng.below(choices.len())])
+                .collect(),
+        };
+        let mut current_score = progress(&current, mode, hulls);
+        let mut stale = 0;
+        while stale < MAX_STALE && !passes_all(&current, mode, hulls) {
+            let mut next = current.clone();
+            let insns = &mut next.insns;
+            match rng.below(3) {
+                0 if insns.len() > 1 => {
+                    insns.remove(rng.below(insns.len()));
+                }
+                1 if insns.len() < max_len => {
+                    insns.insert(
+                        rng.below(insns.len() + 1),
+                        choices[rng.below(choices.len())],
+                    );
+                }
+                _ => {
+                    let i = rng.below(insns.len());
+                    insns[i] = choices[rng.below(choices.len())];
+                }
+            }
+            let s = progress(&next, mode, hulls);
+            stale += 1;
+            if s > current_score {
+                stale = 0;
+            }
+            if s >= current_score {
+                current = next;
+                current_score = s;
+            }
+        }
+        if passes_all(&current, mode, hulls) {
+            let program = prune(current, mode, hulls);
+            if best
+                .as_ref()
+                .is_none_or(|b| program.insns.len() < b.insns.len())
+            {
+                best = Some(program);
+            }
+        }
+    }
+    best
+}
+
+/// Remove instructions from a passing program for as long as it still passes.
+fn prune(mut program: Program, mode: Mode, hulls: &[Hull]) -> Program {
+    let mut i = 0;
+    while i < program.insns.len() {
+        let removed = program.insns.remove(i);
+        if !passes_all(&program, mode, hulls) {
+            program.insns.insert(i, removed);
+            i += 1;
+        }
+    }
+    program
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_format_program() {
+        let text = "NOT A J\nNOT C T\nAND D T\nOR T J\nWALK\n";
+        let program: Program = text.parse().unwrap();
+        assert_eq!(program.insns.len(), 4);
+        assert_eq!(
+            program.insns[1],
+            Insn {
+                op: Op::Not,
+                x: Reg::Sensor(2),
+                y: Reg::T
+            }
+        );
+        assert_eq!(program.to_script(Mode::Walk), text);
+        assert!("AND T A".parse::<Program>().is_err());
+        assert!("XOR A J".parse::<Program>().is_err());
+    }
+
+    #[test]
+    fn replay_failure() {
+        let text = "\
+Didn't make it across:
+
+.................
+.................
+@................
+#####..#.########
+
+.................
+.................
+.@...............
+#####..#.########
+";
+        let hulls = parse_failures(text);
+        assert_eq!(hulls.len(), 1);
+        let hull = &hulls[0];
+        assert_eq!(hull.to_string(), "#####..#.########");
+
+        let program: Program = "NOT A J".parse().unwrap();
+        assert_eq!(hull.replay(Mode::Walk, |s| program.eval(s)), Err(8));
+        assert!(!hull.survives(&program, Mode::Walk));
+    }
+
+    #[test]
+    fn search_walk() {
+        let hulls: Vec<Hull> = [
+            "#####.###########",
+            "#####..#.########",
+            "#####...#########",
+        ]
+        .iter()
+        .map(|s| Hull::from_string(s))
+        .collect();
+        let program = search(Mode::Walk, &hulls, MAX_PROGRAM_LEN, DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(program.insns.len(), 4, "{}", program);
+        for hull in &hulls {
+            assert!(hull.survives(&program, Mode::Walk), "{}", program);
+        }
+    }
+
+    #[test]
+    fn evolve_walk() {
+        let hulls: Vec<Hull> = [
+            "#####.###########",
+            "#####..#.########",
+            "#####...#########",
+            "#####.#..########",
+        ]
+        .iter()
+        .map(|s| Hull::from_string(s))
+        .collect();
+        let program = evolve(Mode::Walk, &hulls, MAX_PROGRAM_LEN, 42, 10).unwrap();
+        assert!(program.insns.len() <= MAX_PROGRAM_LEN);
+        for hull in &hulls {
+            assert!(hull.survives(&program, Mode::Walk), "{}", program);
+        }
+    }
+
+    #[test]
+    fn search_impossible() {
+        let hulls = vec![Hull::from_string("#####.....#####")];
+        assert_eq!(search(Mode::Run, &hulls, 4, DEFAULT_MAX_STATES), None);
+        assert_eq!(evolve(Mode::Run, &hulls, MAX_PROGRAM_LEN, 1, 2), None);
+    }
+
+    #[test]
+    fn evolve_empty() {
+        let hulls = vec![Hull::from_string("#####.###########")];
+        assert_eq!(evolve(Mode::Walk, &hulls, 0, 1, 2), None);
+    }
+}